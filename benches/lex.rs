@@ -0,0 +1,37 @@
+//! Compares the table-driven lexer behind `lex` with the `step`-driven one
+//! behind `lex_bytes` on the same source. Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use zig_lexer::{lex, lex_bytes, Token};
+
+const UNIT: &str = "const std = @import(\"std\");\n/// Doc.\nfn add(a: u32, b: u32) u32 {\n    return a +% b * 0x1f_ff; // comment\n}\nconst s = \"a\\n\\u{1F600}\";\n";
+
+/// Lexes `source` to the end a few times, returning the fastest run and the
+/// number of tokens.
+fn run(source: &str, lex: impl Fn(&str) -> (Token, usize)) -> (Duration, usize) {
+    let mut best = Duration::MAX;
+    let mut count = 0;
+    for _ in 0..5 {
+        let start = Instant::now();
+        let (mut pos, mut n) = (0, 0);
+        while pos < source.len() {
+            pos += black_box(lex(&source[pos..])).1;
+            n += 1;
+        }
+        best = best.min(start.elapsed());
+        count = n;
+    }
+    (best, count)
+}
+
+fn main() {
+    let source = UNIT.repeat(20_000);
+    let (table, table_count) = run(&source, lex);
+    let (step, step_count) = run(&source, |s| lex_bytes(s.as_bytes()));
+    assert_eq!(table_count, step_count);
+    let mb = source.len() as f64 / 1e6;
+    println!("{} bytes, {} tokens", source.len(), table_count);
+    println!("table: {:?} ({:.0} MB/s)", table, mb / table.as_secs_f64());
+    println!("step:  {:?} ({:.0} MB/s)", step, mb / step.as_secs_f64());
+}
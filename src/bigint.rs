@@ -0,0 +1,148 @@
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+
+/// Arbitrary-precision unsigned integer, stored as little-endian `u32` limbs
+/// with no trailing zero limbs.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> BigUint {
+        BigUint { limbs: Vec::new() }
+    }
+
+    pub fn from_u128(mut value: u128) -> BigUint {
+        let mut limbs = Vec::new();
+        while value != 0 {
+            limbs.push(value as u32);
+            value >>= 32;
+        }
+        BigUint { limbs }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn to_u128(&self) -> Option<u128> {
+        if self.limbs.len() > 4 {
+            return None;
+        }
+        Some(
+            self.limbs
+                .iter()
+                .rev()
+                .fold(0, |acc, &limb| acc << 32 | u128::from(limb)),
+        )
+    }
+
+    /// Number of significant bits; zero for zero.
+    pub fn bit_len(&self) -> usize {
+        match self.limbs.last() {
+            Some(&top) => self.limbs.len() * 32 - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    /// Computes `self * mul + add` in place.
+    pub(crate) fn mul_add_small(&mut self, mul: u32, add: u32) {
+        let mut carry = u64::from(add);
+        for limb in &mut self.limbs {
+            let wide = u64::from(*limb) * u64::from(mul) + carry;
+            *limb = wide as u32;
+            carry = wide >> 32;
+        }
+        if carry != 0 {
+            self.limbs.push(carry as u32);
+        }
+        self.normalize();
+    }
+
+    /// Divides in place, returning the remainder.
+    pub(crate) fn div_rem_small(&mut self, div: u32) -> u32 {
+        let mut rem = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let wide = rem << 32 | u64::from(*limb);
+            *limb = (wide / u64::from(div)) as u32;
+            rem = wide % u64::from(div);
+        }
+        self.normalize();
+        rem as u32
+    }
+
+    fn normalize(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(value: u128) -> BigUint {
+        BigUint::from_u128(value)
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return f.pad_integral(true, "", "0");
+        }
+        let mut chunks = Vec::new();
+        let mut value = self.clone();
+        while !value.is_zero() {
+            chunks.push(value.div_rem_small(1_000_000_000));
+        }
+        let mut buf = alloc::string::String::new();
+        for (i, chunk) in chunks.iter().rev().enumerate() {
+            use core::fmt::Write;
+            match i {
+                0 => write!(buf, "{}", chunk)?,
+                _ => write!(buf, "{:09}", chunk)?,
+            }
+        }
+        f.pad_integral(true, "", &buf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_big_uint() {
+        assert_eq!(BigUint::zero().to_string(), "0");
+        assert_eq!(BigUint::from_u128(u128::MAX).to_u128(), Some(u128::MAX));
+        assert_eq!(
+            BigUint::from_u128(u128::MAX).to_string(),
+            u128::MAX.to_string()
+        );
+        let mut big = BigUint::from_u128(u128::MAX);
+        big.mul_add_small(10, 5);
+        assert_eq!(big.to_u128(), None);
+        assert_eq!(big.to_string(), "3402823669209384634633746074317682114555");
+        assert_eq!(big.bit_len(), 132);
+        assert!(big > BigUint::from_u128(u128::MAX));
+        assert_eq!(big.div_rem_small(10), 5);
+        assert_eq!(big, BigUint::from_u128(u128::MAX));
+    }
+}
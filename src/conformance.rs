@@ -0,0 +1,116 @@
+//! Checks the lexer against token dumps from Zig's own tokenizer, stored next
+//! to their sources in `tests/zig/corpus` and generated by `tests/zig/dump.zig`
+//! with the Zig version in `tests/zig/corpus/ZIG_VERSION`.
+
+extern crate std;
+
+use crate::{Lexer, Token, TokenKind};
+use std::fs;
+use std::path::Path;
+use std::string::{String, ToString};
+use std::vec::Vec;
+use std::{format, println};
+
+/// Dumps `source` the way `dump.zig` does: one `tag start end` line per token
+/// Zig's tokenizer would return, ending with `eof`.
+fn zig_tokens(source: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (token, span) in Lexer::new(source) {
+        let (start, mut end) = (span.start, span.end);
+        match token {
+            // Zig has no `&&` token; it is two `&`s.
+            Token::And2 => {
+                lines.push(format!("ampersand {} {}", start, start + 1));
+                lines.push(format!("ampersand {} {}", start + 1, end));
+                continue;
+            }
+            // Zig includes the newline in a `\\` line.
+            Token::MultilineStringLiteralLine if source[end..].starts_with('\n') => end += 1,
+            _ => {}
+        }
+        if let Some(tag) = TokenKind::from(token).zig_tag() {
+            lines.push(format!("{} {} {}", tag, start, end));
+        }
+    }
+    lines.push(format!("eof {} {}", source.len(), source.len()));
+    lines
+}
+
+/// Renders the lines of `expected` and `actual` as a diff, with the source
+/// text of each changed token.
+fn diff(source: &str, expected: &[&str], actual: &[String]) -> String {
+    let (n, m) = (expected.len(), actual.len());
+    let mut lcs = std::vec![std::vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let text = |line: &str| {
+        let mut fields = line.split(' ').skip(1).map(|f| f.parse::<usize>().ok());
+        match (fields.next().flatten(), fields.next().flatten()) {
+            (Some(start), Some(end)) => source.get(start..end).unwrap_or(""),
+            _ => "",
+        }
+    };
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && expected[i] == actual[j] {
+            i += 1;
+            j += 1;
+        } else if j == m || (i < n && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            out += &format!("-{}  {:?}\n", expected[i], text(expected[i]));
+            i += 1;
+        } else {
+            out += &format!("+{}  {:?}\n", actual[j], text(&actual[j]));
+            j += 1;
+        }
+    }
+    out
+}
+
+#[test]
+#[ignore = "the corpus has no dumps yet; generate them with tests/zig/dump.zig"]
+fn test_conformance() {
+    let corpus = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/zig/corpus"));
+    let version = fs::read_to_string(corpus.join("ZIG_VERSION")).unwrap();
+    let header = format!("# zig {}", version.trim_end());
+    let mut paths: Vec<_> = fs::read_dir(corpus)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "zig"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no .zig files in {}", corpus.display());
+
+    let mut failures = Vec::new();
+    for path in &paths {
+        let source = fs::read_to_string(path).unwrap();
+        let expected = match fs::read_to_string(path.with_extension("tokens")) {
+            Ok(expected) => expected,
+            Err(_) => {
+                println!("{}: no dump, run tests/zig/dump.zig", path.display());
+                failures.push(path.display().to_string());
+                continue;
+            }
+        };
+        let mut expected = expected.lines();
+        assert_eq!(expected.next(), Some(&*header), "{}", path.display());
+        let expected: Vec<&str> = expected.collect();
+        let actual = zig_tokens(&source);
+        if expected != actual {
+            println!("{}:\n{}", path.display(), diff(&source, &expected, &actual));
+            failures.push(path.display().to_string());
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "token dumps missing or differ for {:?}",
+        failures
+    );
+}
@@ -0,0 +1,706 @@
+//! Table-driven version of `step` over bytes.
+//!
+//! Every `State`, including its flags, is packed into a `u8` id, and the
+//! transitions for each id and byte class are computed at compile time by
+//! running `step` itself. The hot loop only looks up the next id per byte; when
+//! the table says to stop, the unpacked state is handed back to `step` to
+//! produce the token, so the result is the same as the character-driven lexer.
+
+use crate::skip::{skip, Skip};
+use crate::{
+    finish, record_scan, step, EscapeKind, InvalidCharacterKind, State, Step, Token,
+    DEFAULT_OPTIONS,
+};
+
+/// Number of distinct `State` values, counting every combination of flags.
+pub(crate) const STATE_COUNT: usize = 201;
+
+const fn escape_kind_id(escape_kind: EscapeKind) -> u8 {
+    match escape_kind {
+        EscapeKind::RawIdentifier => 0,
+        EscapeKind::StringLiteral => 1,
+        EscapeKind::CharacterLiteral => 2,
+    }
+}
+
+const fn escape_kind_from_id(id: u8) -> EscapeKind {
+    match id {
+        0 => EscapeKind::RawIdentifier,
+        1 => EscapeKind::StringLiteral,
+        _ => EscapeKind::CharacterLiteral,
+    }
+}
+
+const fn invalid_kind_from_id(id: u8) -> InvalidCharacterKind {
+    match id {
+        0 => InvalidCharacterKind::NonAscii,
+        1 => InvalidCharacterKind::ControlCharacter,
+        2 => InvalidCharacterKind::Backslash,
+        3 => InvalidCharacterKind::LoneCarriageReturn,
+        4 => InvalidCharacterKind::UnknownSymbol,
+        _ => InvalidCharacterKind::LineSeparator,
+    }
+}
+
+/// Packs a state and its flags into a single id below `STATE_COUNT`.
+pub(crate) const fn encode_state(state: State) -> u8 {
+    match state {
+        State::Start => 0,
+        State::Invalid { kind } => 1 + kind as u8,
+        State::Whitespace => 7,
+        State::CarriageReturn => 8,
+        State::WhitespaceCarriageReturn => 9,
+        State::Bang => 10,
+        State::Percent => 11,
+        State::And => 12,
+        State::Star => 13,
+        State::StarPercent => 14,
+        State::StarOr => 15,
+        State::Plus => 16,
+        State::PlusPercent => 17,
+        State::PlusOr => 18,
+        State::Minus => 19,
+        State::MinusPercent => 20,
+        State::MinusOr => 21,
+        State::Dot => 22,
+        State::DotStar => 23,
+        State::Dot2 => 24,
+        State::Slash => 25,
+        State::Slash2 => 26,
+        State::Slash3 => 27,
+        State::LAngle => 28,
+        State::LAngle2 => 29,
+        State::LAngle2Or => 30,
+        State::Equal => 31,
+        State::RAngle => 32,
+        State::RAngle2 => 33,
+        State::At => 34,
+        State::Caret => 35,
+        State::Or => 36,
+        State::Comment => 37,
+        State::DocComment => 38,
+        State::ContainerDocComment => 39,
+        State::InvalidLine => 40,
+        State::LineCarriageReturn => 41,
+        State::Backslash => 42,
+        State::MultilineStringLiteralLine => 43,
+        State::Identifier => 44,
+        State::Builtin => 45,
+        State::StringLiteral {
+            is_empty,
+            escape_kind,
+            has_invalid_escape,
+        } => 46 + is_empty as u8 + 2 * escape_kind_id(escape_kind) + 6 * has_invalid_escape as u8,
+        State::StringEscape {
+            is_empty,
+            escape_kind,
+            has_invalid_escape,
+        } => 58 + is_empty as u8 + 2 * escape_kind_id(escape_kind) + 6 * has_invalid_escape as u8,
+        State::StringEscapeHex1 {
+            escape_kind,
+            has_invalid_escape,
+        } => 70 + escape_kind_id(escape_kind) + 3 * has_invalid_escape as u8,
+        State::StringEscapeHex2 {
+            escape_kind,
+            has_invalid_escape,
+        } => 76 + escape_kind_id(escape_kind) + 3 * has_invalid_escape as u8,
+        State::StringEscapeUnicode1 {
+            escape_kind,
+            has_invalid_escape,
+        } => 82 + escape_kind_id(escape_kind) + 3 * has_invalid_escape as u8,
+        State::StringEscapeUnicode2 {
+            escape_kind,
+            has_invalid_escape,
+        } => 88 + escape_kind_id(escape_kind) + 3 * has_invalid_escape as u8,
+        State::StringEscapeUnicode3 {
+            escape_kind,
+            has_invalid_escape,
+        } => 94 + escape_kind_id(escape_kind) + 3 * has_invalid_escape as u8,
+        State::Zero => 100,
+        State::Number {
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => 101 + has_invalid_characters as u8 + 2 * has_duplicate_underscore as u8,
+        State::NumberUnderscore {
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => 105 + has_invalid_characters as u8 + 2 * has_duplicate_underscore as u8,
+        State::NumberExponent {
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => 109 + has_invalid_characters as u8 + 2 * has_duplicate_underscore as u8,
+        State::NumberExponentSign {
+            is_unterminated,
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => {
+            113 + is_unterminated as u8
+                + 2 * has_invalid_characters as u8
+                + 4 * has_duplicate_underscore as u8
+        }
+        State::NumberExponentSignUnderscore {
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => 121 + has_invalid_characters as u8 + 2 * has_duplicate_underscore as u8,
+        State::NumberBinary {
+            is_unterminated,
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => {
+            125 + is_unterminated as u8
+                + 2 * has_invalid_characters as u8
+                + 4 * has_duplicate_underscore as u8
+        }
+        State::NumberBinaryUnderscore {
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => 133 + has_invalid_characters as u8 + 2 * has_duplicate_underscore as u8,
+        State::NumberOctal {
+            is_unterminated,
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => {
+            137 + is_unterminated as u8
+                + 2 * has_invalid_characters as u8
+                + 4 * has_duplicate_underscore as u8
+        }
+        State::NumberOctalUnderscore {
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => 145 + has_invalid_characters as u8 + 2 * has_duplicate_underscore as u8,
+        State::NumberHex {
+            is_unterminated,
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => {
+            149 + is_unterminated as u8
+                + 2 * has_invalid_characters as u8
+                + 4 * has_duplicate_underscore as u8
+        }
+        State::NumberHexUnderscore {
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => 157 + has_invalid_characters as u8 + 2 * has_duplicate_underscore as u8,
+        State::NumberHexDot {
+            is_unterminated,
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => {
+            161 + is_unterminated as u8
+                + 2 * has_invalid_characters as u8
+                + 4 * has_duplicate_underscore as u8
+        }
+        State::NumberHexDotUnderscore {
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => 169 + has_invalid_characters as u8 + 2 * has_duplicate_underscore as u8,
+        State::NumberHexExponent {
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => 173 + has_invalid_characters as u8 + 2 * has_duplicate_underscore as u8,
+        State::NumberHexExponentSign {
+            is_unterminated,
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => {
+            177 + is_unterminated as u8
+                + 2 * has_invalid_characters as u8
+                + 4 * has_duplicate_underscore as u8
+        }
+        State::NumberHexExponentSignUnderscore {
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => 185 + has_invalid_characters as u8 + 2 * has_duplicate_underscore as u8,
+        State::NumberDot {
+            is_unterminated,
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => {
+            189 + is_unterminated as u8
+                + 2 * has_invalid_characters as u8
+                + 4 * has_duplicate_underscore as u8
+        }
+        State::NumberDotUnderscore {
+            has_invalid_characters,
+            has_duplicate_underscore,
+        } => 197 + has_invalid_characters as u8 + 2 * has_duplicate_underscore as u8,
+    }
+}
+
+pub(crate) const fn decode_state(id: u8) -> State {
+    match id {
+        0 => State::Start,
+        1..=6 => {
+            let i = id - 1;
+            State::Invalid {
+                kind: invalid_kind_from_id(i % 6),
+            }
+        }
+        7 => State::Whitespace,
+        8 => State::CarriageReturn,
+        9 => State::WhitespaceCarriageReturn,
+        10 => State::Bang,
+        11 => State::Percent,
+        12 => State::And,
+        13 => State::Star,
+        14 => State::StarPercent,
+        15 => State::StarOr,
+        16 => State::Plus,
+        17 => State::PlusPercent,
+        18 => State::PlusOr,
+        19 => State::Minus,
+        20 => State::MinusPercent,
+        21 => State::MinusOr,
+        22 => State::Dot,
+        23 => State::DotStar,
+        24 => State::Dot2,
+        25 => State::Slash,
+        26 => State::Slash2,
+        27 => State::Slash3,
+        28 => State::LAngle,
+        29 => State::LAngle2,
+        30 => State::LAngle2Or,
+        31 => State::Equal,
+        32 => State::RAngle,
+        33 => State::RAngle2,
+        34 => State::At,
+        35 => State::Caret,
+        36 => State::Or,
+        37 => State::Comment,
+        38 => State::DocComment,
+        39 => State::ContainerDocComment,
+        40 => State::InvalidLine,
+        41 => State::LineCarriageReturn,
+        42 => State::Backslash,
+        43 => State::MultilineStringLiteralLine,
+        44 => State::Identifier,
+        45 => State::Builtin,
+        46..=57 => {
+            let i = id - 46;
+            State::StringLiteral {
+                is_empty: i % 2 == 1,
+                escape_kind: escape_kind_from_id(i / 2 % 3),
+                has_invalid_escape: i / 6 % 2 == 1,
+            }
+        }
+        58..=69 => {
+            let i = id - 58;
+            State::StringEscape {
+                is_empty: i % 2 == 1,
+                escape_kind: escape_kind_from_id(i / 2 % 3),
+                has_invalid_escape: i / 6 % 2 == 1,
+            }
+        }
+        70..=75 => {
+            let i = id - 70;
+            State::StringEscapeHex1 {
+                escape_kind: escape_kind_from_id(i % 3),
+                has_invalid_escape: i / 3 % 2 == 1,
+            }
+        }
+        76..=81 => {
+            let i = id - 76;
+            State::StringEscapeHex2 {
+                escape_kind: escape_kind_from_id(i % 3),
+                has_invalid_escape: i / 3 % 2 == 1,
+            }
+        }
+        82..=87 => {
+            let i = id - 82;
+            State::StringEscapeUnicode1 {
+                escape_kind: escape_kind_from_id(i % 3),
+                has_invalid_escape: i / 3 % 2 == 1,
+            }
+        }
+        88..=93 => {
+            let i = id - 88;
+            State::StringEscapeUnicode2 {
+                escape_kind: escape_kind_from_id(i % 3),
+                has_invalid_escape: i / 3 % 2 == 1,
+            }
+        }
+        94..=99 => {
+            let i = id - 94;
+            State::StringEscapeUnicode3 {
+                escape_kind: escape_kind_from_id(i % 3),
+                has_invalid_escape: i / 3 % 2 == 1,
+            }
+        }
+        100 => State::Zero,
+        101..=104 => {
+            let i = id - 101;
+            State::Number {
+                has_invalid_characters: i % 2 == 1,
+                has_duplicate_underscore: i / 2 % 2 == 1,
+            }
+        }
+        105..=108 => {
+            let i = id - 105;
+            State::NumberUnderscore {
+                has_invalid_characters: i % 2 == 1,
+                has_duplicate_underscore: i / 2 % 2 == 1,
+            }
+        }
+        109..=112 => {
+            let i = id - 109;
+            State::NumberExponent {
+                has_invalid_characters: i % 2 == 1,
+                has_duplicate_underscore: i / 2 % 2 == 1,
+            }
+        }
+        113..=120 => {
+            let i = id - 113;
+            State::NumberExponentSign {
+                is_unterminated: i % 2 == 1,
+                has_invalid_characters: i / 2 % 2 == 1,
+                has_duplicate_underscore: i / 4 % 2 == 1,
+            }
+        }
+        121..=124 => {
+            let i = id - 121;
+            State::NumberExponentSignUnderscore {
+                has_invalid_characters: i % 2 == 1,
+                has_duplicate_underscore: i / 2 % 2 == 1,
+            }
+        }
+        125..=132 => {
+            let i = id - 125;
+            State::NumberBinary {
+                is_unterminated: i % 2 == 1,
+                has_invalid_characters: i / 2 % 2 == 1,
+                has_duplicate_underscore: i / 4 % 2 == 1,
+            }
+        }
+        133..=136 => {
+            let i = id - 133;
+            State::NumberBinaryUnderscore {
+                has_invalid_characters: i % 2 == 1,
+                has_duplicate_underscore: i / 2 % 2 == 1,
+            }
+        }
+        137..=144 => {
+            let i = id - 137;
+            State::NumberOctal {
+                is_unterminated: i % 2 == 1,
+                has_invalid_characters: i / 2 % 2 == 1,
+                has_duplicate_underscore: i / 4 % 2 == 1,
+            }
+        }
+        145..=148 => {
+            let i = id - 145;
+            State::NumberOctalUnderscore {
+                has_invalid_characters: i % 2 == 1,
+                has_duplicate_underscore: i / 2 % 2 == 1,
+            }
+        }
+        149..=156 => {
+            let i = id - 149;
+            State::NumberHex {
+                is_unterminated: i % 2 == 1,
+                has_invalid_characters: i / 2 % 2 == 1,
+                has_duplicate_underscore: i / 4 % 2 == 1,
+            }
+        }
+        157..=160 => {
+            let i = id - 157;
+            State::NumberHexUnderscore {
+                has_invalid_characters: i % 2 == 1,
+                has_duplicate_underscore: i / 2 % 2 == 1,
+            }
+        }
+        161..=168 => {
+            let i = id - 161;
+            State::NumberHexDot {
+                is_unterminated: i % 2 == 1,
+                has_invalid_characters: i / 2 % 2 == 1,
+                has_duplicate_underscore: i / 4 % 2 == 1,
+            }
+        }
+        169..=172 => {
+            let i = id - 169;
+            State::NumberHexDotUnderscore {
+                has_invalid_characters: i % 2 == 1,
+                has_duplicate_underscore: i / 2 % 2 == 1,
+            }
+        }
+        173..=176 => {
+            let i = id - 173;
+            State::NumberHexExponent {
+                has_invalid_characters: i % 2 == 1,
+                has_duplicate_underscore: i / 2 % 2 == 1,
+            }
+        }
+        177..=184 => {
+            let i = id - 177;
+            State::NumberHexExponentSign {
+                is_unterminated: i % 2 == 1,
+                has_invalid_characters: i / 2 % 2 == 1,
+                has_duplicate_underscore: i / 4 % 2 == 1,
+            }
+        }
+        185..=188 => {
+            let i = id - 185;
+            State::NumberHexExponentSignUnderscore {
+                has_invalid_characters: i % 2 == 1,
+                has_duplicate_underscore: i / 2 % 2 == 1,
+            }
+        }
+        189..=196 => {
+            let i = id - 189;
+            State::NumberDot {
+                is_unterminated: i % 2 == 1,
+                has_invalid_characters: i / 2 % 2 == 1,
+                has_duplicate_underscore: i / 4 % 2 == 1,
+            }
+        }
+        197..=200 => {
+            let i = id - 197;
+            State::NumberDotUnderscore {
+                has_invalid_characters: i % 2 == 1,
+                has_duplicate_underscore: i / 2 % 2 == 1,
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Table entry for transitions that do not continue the current token.
+const STOP: u8 = u8::MAX;
+/// Table entry for a non-ASCII character whose transition depends on which
+/// character it is. The character is decoded and handed to `step`.
+const DECODE: u8 = u8::MAX - 1;
+
+/// Column for the lead byte of any non-ASCII character. `step` only
+/// distinguishes between non-ASCII characters at the start of a token and in
+/// a run of invalid characters, where their kind matters.
+const NON_ASCII: usize = 128;
+/// Column for UTF-8 continuation bytes, which stay in the current state.
+const CONTINUATION: usize = 129;
+const CLASS_COUNT: usize = 130;
+
+const fn byte_classes() -> [u8; 256] {
+    let mut classes = [0; 256];
+    let mut b = 0;
+    while b < 256 {
+        classes[b] = match b {
+            0x00..=0x7f => b as u8,
+            0x80..=0xbf => CONTINUATION as u8,
+            _ => NON_ASCII as u8,
+        };
+        b += 1;
+    }
+    classes
+}
+
+const fn transitions() -> [[u8; CLASS_COUNT]; STATE_COUNT] {
+    let mut table = [[STOP; CLASS_COUNT]; STATE_COUNT];
+    let mut id = 0;
+    while id < STATE_COUNT {
+        let mut class = 0;
+        while class < CLASS_COUNT {
+            let c = match class {
+                NON_ASCII => {
+                    if let State::Start | State::Invalid { .. } = decode_state(id as u8) {
+                        table[id][class] = DECODE;
+                        class += 1;
+                        continue;
+                    }
+                    '\u{e9}'
+                }
+                CONTINUATION => {
+                    table[id][class] = id as u8;
+                    class += 1;
+                    continue;
+                }
+                _ => class as u8 as char,
+            };
+            table[id][class] = match decode_step(decode_state(id as u8), Some(c)) {
+                Some(next) => encode_state(next),
+                None => STOP,
+            };
+            class += 1;
+        }
+        id += 1;
+    }
+    table
+}
+
+/// States that loop on long runs of bytes, which are skipped in bulk.
+const fn skips() -> [Option<Skip>; STATE_COUNT] {
+    let mut skips = [None; STATE_COUNT];
+    let mut id = 0;
+    while id < STATE_COUNT {
+        skips[id] = match decode_state(id as u8) {
+            State::Whitespace => Some(Skip::Whitespace),
+            State::Comment
+            | State::DocComment
+            | State::ContainerDocComment
+            | State::MultilineStringLiteralLine => Some(Skip::Line),
+            State::Identifier | State::Builtin => Some(Skip::Identifier),
+            _ => None,
+        };
+        id += 1;
+    }
+    skips
+}
+
+static BYTE_CLASSES: [u8; 256] = byte_classes();
+static TRANSITIONS: [[u8; CLASS_COUNT]; STATE_COUNT] = transitions();
+static SKIPS: [Option<Skip>; STATE_COUNT] = skips();
+
+/// The state `c` continues `state` in, if any.
+const fn decode_step(mut state: State, c: Option<char>) -> Option<State> {
+    loop {
+        match step(state, c, DEFAULT_OPTIONS) {
+            Step::Continue(next) => return Some(next),
+            Step::Reprocess(next) => state = next,
+            _ => return None,
+        }
+    }
+}
+
+/// Lexes a single token like `lex_token`, returning the same result.
+pub(crate) fn lex_token(s: &str) -> (Token, usize) {
+    let bytes = s.as_bytes();
+    let (mut id, mut last_id) = (encode_state(State::Start), 0);
+    let mut pos = 0;
+    while let Some(&b) = bytes.get(pos) {
+        let next = match TRANSITIONS[id as usize][BYTE_CLASSES[b as usize] as usize] {
+            STOP => break,
+            // The rest of the character is continuation bytes, which stay in
+            // the new state.
+            DECODE => match decode_step(decode_state(id), s[pos..].chars().next()) {
+                Some(next) => encode_state(next),
+                None => break,
+            },
+            next => next,
+        };
+        last_id = id;
+        id = next;
+        pos += 1;
+        if let Some(kind) = SKIPS[id as usize] {
+            pos = skip(bytes, pos, kind);
+        }
+    }
+    record_scan(pos);
+    let c = s[pos..].chars().next();
+    let mut state = decode_state(id);
+    loop {
+        match step(state, c, DEFAULT_OPTIONS) {
+            Step::Reprocess(next) => state = next,
+            Step::Abort(token) => return (token, pos),
+            Step::End(token) => return (token, pos + c.map_or(0, char::len_utf8)),
+            // Only reached after a `.` or `\r`, which is one byte long and
+            // never skipped in bulk.
+            Step::Backtrack => return (finish(decode_state(last_id)), pos - 1),
+            Step::Continue(_) => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::*;
+    use crate::LexOptions;
+
+    #[test]
+    fn test_state_ids() {
+        for id in 0..STATE_COUNT as u8 {
+            assert_eq!(encode_state(decode_state(id)), id);
+        }
+        assert!(STATE_COUNT < DECODE as usize);
+    }
+
+    /// Every state `step` can reach has an id below `STATE_COUNT` that decodes
+    /// back to it. Together with `test_state_ids`, which checks that every id
+    /// is used exactly once, this catches a missed or overlapping arm in
+    /// `encode_state` or `decode_state`.
+    #[test]
+    fn test_reachable_states() {
+        let chars = (0..0x80u8)
+            .map(char::from)
+            .chain(['é', '\u{85}', '\u{2028}', '\u{feff}', '😀'])
+            .chain([core::char::REPLACEMENT_CHARACTER])
+            .map(Some)
+            .chain([None]);
+        let mut seen = [false; STATE_COUNT];
+        seen[encode_state(State::Start) as usize] = true;
+        let mut stack = std::vec![State::Start];
+        while let Some(state) = stack.pop() {
+            for (newlines, strict) in [(false, false), (false, true), (true, false), (true, true)] {
+                let options = LexOptions { newlines, strict };
+                for c in chars.clone() {
+                    let next = match step(state, c, options) {
+                        Step::Continue(next) | Step::Reprocess(next) => next,
+                        _ => continue,
+                    };
+                    let id = encode_state(next);
+                    assert!((id as usize) < STATE_COUNT, "{:?}", next);
+                    assert_eq!(decode_state(id), next);
+                    if !seen[id as usize] {
+                        seen[id as usize] = true;
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_step() {
+        let alphabet = [
+            "", " ", "\n", "a", "_", "0", "1", "x", "e", "p", "+", ".", "/", "!", "\\", "\"", "'",
+            "@", "u", "{", "}", "*", "|", "<", "=", "%", "é", "😀", "\r", "\t", "$", "\u{85}",
+            "\u{2028}",
+        ];
+        let mut text = [0u8; 16];
+        for a in alphabet {
+            for b in alphabet {
+                for c in alphabet {
+                    for d in ["", "x", ".", "\"", "'", "\n"] {
+                        let mut len = 0;
+                        for part in [a, b, c, d] {
+                            text[len..len + part.len()].copy_from_slice(part.as_bytes());
+                            len += part.len();
+                        }
+                        let s = core::str::from_utf8(&text[..len]).unwrap();
+                        assert_eq!(
+                            lex_token(s),
+                            crate::lex_token(s, DEFAULT_OPTIONS),
+                            "{:?}",
+                            s
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_long_tokens() {
+        let mut text = [0u8; 80];
+        for start in ["", "//", "///", "//!", "\\\\", " ", "\n", "a", "@a", "_"] {
+            for fill in [" ", "\t", "a", "Z", "9", "_", "é", "-", "\r\n"] {
+                for end in ["", "\n", " ", "x", ".", "é", "\u{80}", "$"] {
+                    for count in [3, 7, 8, 9, 16, 17, 24] {
+                        let mut len = 0;
+                        let fills = core::iter::repeat_n(fill, count);
+                        for part in core::iter::once(start).chain(fills).chain([end]) {
+                            text[len..len + part.len()].copy_from_slice(part.as_bytes());
+                            len += part.len();
+                        }
+                        let s = core::str::from_utf8(&text[..len]).unwrap();
+                        assert_eq!(
+                            lex_token(s),
+                            crate::lex_token(s, DEFAULT_OPTIONS),
+                            "{:?}",
+                            s
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,591 @@
+use crate::{EscapeErrorKind, InvalidCharacterKind, Span, Token, Unescape};
+use core::fmt;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DiagnosticKind {
+    /// The invalid token's kind and first character.
+    InvalidCharacter(InvalidCharacterKind, char),
+    InvalidUtf8,
+    UnterminatedStringLiteral,
+    UnterminatedCharacterLiteral,
+    UnterminatedRawIdentifier,
+    EmptyCharacterLiteral,
+    EmptyRawIdentifier,
+    InvalidEscape(EscapeErrorKind),
+    UnterminatedNumber,
+    InvalidDigit,
+    DuplicateUnderscore,
+    LeadingZero,
+}
+
+impl DiagnosticKind {
+    pub fn message(self) -> &'static str {
+        match self {
+            DiagnosticKind::InvalidCharacter(kind, _) => match kind {
+                InvalidCharacterKind::NonAscii => "non-ASCII character",
+                InvalidCharacterKind::ControlCharacter => "control character",
+                InvalidCharacterKind::Backslash => "stray backslash",
+                InvalidCharacterKind::LoneCarriageReturn => {
+                    "carriage return not followed by a newline"
+                }
+                InvalidCharacterKind::UnknownSymbol => "unexpected character",
+                InvalidCharacterKind::LineSeparator => "line or paragraph separator",
+            },
+            DiagnosticKind::InvalidUtf8 => "invalid UTF-8",
+            DiagnosticKind::UnterminatedStringLiteral => "unterminated string literal",
+            DiagnosticKind::UnterminatedCharacterLiteral => "unterminated character literal",
+            DiagnosticKind::UnterminatedRawIdentifier => "unterminated raw identifier",
+            DiagnosticKind::EmptyCharacterLiteral => "empty character literal",
+            DiagnosticKind::EmptyRawIdentifier => "empty raw identifier",
+            DiagnosticKind::InvalidEscape(kind) => match kind {
+                EscapeErrorKind::InvalidEscapeCharacter => "invalid escape character",
+                EscapeErrorKind::InvalidHexEscape => "expected two hex digits after \\x",
+                EscapeErrorKind::InvalidUnicodeEscape => "expected \\u{...} with hex digits",
+                EscapeErrorKind::UnicodeEscapeTooLong => "more than six hex digits in \\u{...}",
+                EscapeErrorKind::CodepointTooLarge => "unicode escape is above 0x10FFFF",
+                EscapeErrorKind::SurrogateCodepoint => "unicode escape is a surrogate code point",
+                EscapeErrorKind::NullInIdentifier => "identifier cannot contain null bytes",
+                EscapeErrorKind::Unterminated => "unterminated literal",
+                EscapeErrorKind::EmptyCharacterLiteral => "empty character literal",
+                EscapeErrorKind::MultipleCharacters => "more than one character in literal",
+            },
+            DiagnosticKind::UnterminatedNumber => "expected digits",
+            DiagnosticKind::InvalidDigit => "invalid digit in number literal",
+            DiagnosticKind::DuplicateUnderscore => "repeated underscore in number literal",
+            DiagnosticKind::LeadingZero => "leading zero in decimal number literal",
+        }
+    }
+}
+
+/// The message, followed by the code point for invalid characters, as in
+/// "unexpected character U+0024 '$'".
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            DiagnosticKind::InvalidCharacter(_, c) => write!(
+                f,
+                "{} U+{:04X} '{}'",
+                self.message(),
+                c as u32,
+                c.escape_debug()
+            ),
+            _ => f.write_str(self.message()),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct LexDiagnostic {
+    pub kind: DiagnosticKind,
+    pub span: Span,
+    pub message: &'static str,
+}
+
+impl LexDiagnostic {
+    fn new(kind: DiagnosticKind, span: Span) -> LexDiagnostic {
+        LexDiagnostic {
+            kind,
+            span,
+            message: kind.message(),
+        }
+    }
+}
+
+/// Walks the characters of a number literal, tracking which digits are valid
+/// in each part the way the `State::Number*` states do.
+#[derive(Debug, Clone)]
+struct NumberScan<'a> {
+    text: &'a str,
+    pos: usize,
+    radix: u32,
+    in_exponent: bool,
+    after_underscore: bool,
+    report_unterminated: bool,
+    report_invalid: bool,
+    report_duplicate: bool,
+    found_invalid: bool,
+}
+
+impl<'a> NumberScan<'a> {
+    fn new(text: &'a str, flags: (bool, bool, bool)) -> NumberScan<'a> {
+        let (pos, radix) = match text.get(..2) {
+            Some("0b") => (2, 2),
+            Some("0o") => (2, 8),
+            Some("0x") => (2, 16),
+            _ => (0, 10),
+        };
+        NumberScan {
+            text,
+            pos,
+            radix,
+            in_exponent: false,
+            after_underscore: false,
+            report_unterminated: flags.0,
+            report_invalid: flags.1,
+            report_duplicate: flags.2,
+            found_invalid: false,
+        }
+    }
+
+    fn next(&mut self) -> Option<(DiagnosticKind, Span)> {
+        while let Some(c) = self.text[self.pos..].chars().next() {
+            let start = self.pos;
+            self.pos += c.len_utf8();
+            // A letter just after a `_` is never an exponent, as in the lexer.
+            let after_underscore = core::mem::replace(&mut self.after_underscore, c == '_');
+            match (c, self.radix, self.in_exponent) {
+                ('_', _, _) if self.report_duplicate && self.text[self.pos..].starts_with('_') => {
+                    self.pos += 1;
+                    self.after_underscore = false;
+                    let span = Span::new(start, self.pos);
+                    return Some((DiagnosticKind::DuplicateUnderscore, span));
+                }
+                ('_', _, _) | ('.', _, false) | ('+', _, true) | ('-', _, true) => {}
+                ('e', 10, false) | ('E', 10, false) if !after_underscore => self.in_exponent = true,
+                ('p', 10, false) | ('P', 10, false) | ('p', 16, false) | ('P', 16, false)
+                    if !after_underscore =>
+                {
+                    self.in_exponent = true;
+                    self.radix = 16;
+                }
+                _ if self.report_invalid && c.to_digit(self.radix).is_none() => {
+                    self.found_invalid = true;
+                    return Some((DiagnosticKind::InvalidDigit, Span::new(start, self.pos)));
+                }
+                _ => {}
+            }
+        }
+        // Strict lexing marks digits and `_` after a leading zero as invalid.
+        if self.report_invalid && !self.found_invalid {
+            self.report_invalid = false;
+            if let [b'0', b'0'..=b'9' | b'_', ..] = self.text.as_bytes() {
+                return Some((DiagnosticKind::LeadingZero, Span::new(0, 1)));
+            }
+        }
+        if self.report_unterminated {
+            self.report_unterminated = false;
+            let last = self.text.chars().next_back().map_or(0, char::len_utf8);
+            let span = Span::new(self.text.len() - last, self.text.len());
+            return Some((DiagnosticKind::UnterminatedNumber, span));
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Inner<'a> {
+    Done,
+    Single(DiagnosticKind, Span),
+    Escapes {
+        unescape: Unescape<'a>,
+        opening: Span,
+        unterminated: DiagnosticKind,
+    },
+    Number(NumberScan<'a>),
+}
+
+/// Iterator over the diagnostics of one token. See [`diagnostics`].
+#[derive(Debug, Clone)]
+pub struct Diagnostics<'a> {
+    start: usize,
+    inner: Inner<'a>,
+    bytes: &'a [u8],
+    /// Where to look for the next invalid UTF-8 sequence in `bytes`.
+    utf8_pos: usize,
+}
+
+impl<'a> Iterator for Diagnostics<'a> {
+    type Item = LexDiagnostic;
+
+    fn next(&mut self) -> Option<LexDiagnostic> {
+        let item = match &mut self.inner {
+            Inner::Done => None,
+            Inner::Single(kind, span) => {
+                let item = (*kind, *span);
+                self.inner = Inner::Done;
+                Some(item)
+            }
+            Inner::Escapes {
+                unescape,
+                opening,
+                unterminated,
+            } => unescape.find_map(Result::err).map(|err| match err.kind {
+                EscapeErrorKind::Unterminated => (*unterminated, *opening),
+                kind => (DiagnosticKind::InvalidEscape(kind), err.span),
+            }),
+            Inner::Number(scan) => scan.next(),
+        };
+        let (kind, span) = match item {
+            Some(item) => item,
+            None => {
+                self.inner = Inner::Done;
+                self.next_invalid_utf8()?
+            }
+        };
+        let span = Span::new(self.start + span.start, self.start + span.end);
+        Some(LexDiagnostic::new(kind, span))
+    }
+}
+
+impl<'a> Diagnostics<'a> {
+    fn next_invalid_utf8(&mut self) -> Option<(DiagnosticKind, Span)> {
+        let rest = &self.bytes[self.utf8_pos..];
+        let err = match core::str::from_utf8(rest) {
+            Ok(_) => {
+                self.utf8_pos = self.bytes.len();
+                return None;
+            }
+            Err(err) => err,
+        };
+        let start = self.utf8_pos + err.valid_up_to();
+        self.utf8_pos = start + err.error_len().unwrap_or(self.bytes.len() - start);
+        Some((DiagnosticKind::InvalidUtf8, Span::new(start, self.utf8_pos)))
+    }
+}
+
+impl<'a> core::iter::FusedIterator for Diagnostics<'a> {}
+
+/// Explains what is wrong with `token`, whose source text is `text` starting
+/// at byte offset `start`. Spans in the returned diagnostics are absolute and
+/// point at the offending escape, digit or underscore rather than the whole
+/// token. Tokens for which [`Token::is_error`] is false yield nothing, except
+/// raw identifiers with a `\x00` or `\u{0}` escape, which Zig rejects only
+/// after tokenizing.
+pub fn diagnostics(token: Token, text: &str, start: usize) -> Diagnostics<'_> {
+    let mut diagnostics = diagnostics_bytes(token, text.as_bytes(), start);
+    diagnostics.utf8_pos = text.len();
+    diagnostics
+}
+
+/// Like [`diagnostics`], but for tokens from [`lex_bytes`](crate::lex_bytes)
+/// or [`BytesLexer`](crate::BytesLexer). Invalid UTF-8 sequences inside
+/// comments and literals, which the lexer keeps as part of the token, are
+/// reported after the token's other diagnostics, one for each sequence.
+pub fn diagnostics_bytes(token: Token, text: &[u8], start: usize) -> Diagnostics<'_> {
+    let whole = Span::new(0, text.len());
+    let inner = match token {
+        // Zig's tokenizer accepts a raw identifier whose escapes produce a
+        // zero byte, so it is not an error token but still gets a diagnostic.
+        _ if !token.is_error() && !matches!(token, Token::RawIdentifier { .. }) => Inner::Done,
+        Token::Invalid { kind, character } => {
+            Inner::Single(DiagnosticKind::InvalidCharacter(kind, character), whole)
+        }
+        Token::InvalidUtf8 => Inner::Single(DiagnosticKind::InvalidUtf8, whole),
+        Token::RawIdentifier { is_empty: true, .. } => {
+            Inner::Single(DiagnosticKind::EmptyRawIdentifier, whole)
+        }
+        Token::CharacterLiteral {
+            is_empty: true,
+            is_unterminated: false,
+            ..
+        } => Inner::Single(DiagnosticKind::EmptyCharacterLiteral, whole),
+        Token::StringLiteral { .. } => Inner::Escapes {
+            unescape: Unescape::new(text, b"\"", b'"'),
+            opening: Span::new(0, 1),
+            unterminated: DiagnosticKind::UnterminatedStringLiteral,
+        },
+        Token::CharacterLiteral { .. } => Inner::Escapes {
+            unescape: Unescape::new(text, b"'", b'\''),
+            opening: Span::new(0, 1),
+            unterminated: DiagnosticKind::UnterminatedCharacterLiteral,
+        },
+        Token::RawIdentifier { .. } => Inner::Escapes {
+            unescape: Unescape::new(text, b"@\"", b'"'),
+            opening: Span::new(0, 2),
+            unterminated: DiagnosticKind::UnterminatedRawIdentifier,
+        },
+        Token::IntegerLiteral {
+            is_unterminated,
+            has_invalid_characters,
+            has_duplicate_underscore,
+            ..
+        }
+        | Token::FloatLiteral {
+            is_unterminated,
+            has_invalid_characters,
+            has_duplicate_underscore,
+            ..
+        } => Inner::Number(NumberScan::new(
+            // Number literals are ASCII.
+            core::str::from_utf8(text).unwrap_or_default(),
+            (
+                is_unterminated,
+                has_invalid_characters,
+                has_duplicate_underscore,
+            ),
+        )),
+        _ => Inner::Done,
+    };
+    Diagnostics {
+        start,
+        inner,
+        bytes: text,
+        utf8_pos: match token {
+            Token::InvalidUtf8 => text.len(),
+            _ => 0,
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{lex, lex_bytes, lex_with_options, LexOptions};
+
+    fn check(text: &str, expected: &[(DiagnosticKind, usize, usize)]) {
+        check_with(text, LexOptions::default(), expected);
+    }
+
+    fn check_with(text: &str, options: LexOptions, expected: &[(DiagnosticKind, usize, usize)]) {
+        let (token, len) = lex_with_options(text, options);
+        assert_eq!(len, text.len(), "{}", text);
+        let mut diagnostics = diagnostics(token, text, 10);
+        for &(kind, start, end) in expected {
+            let diagnostic = diagnostics.next().unwrap();
+            assert_eq!(
+                (diagnostic.kind, diagnostic.span),
+                (kind, Span::new(start + 10, end + 10)),
+                "{}",
+                text
+            );
+            assert_eq!(diagnostic.message, kind.message());
+        }
+        assert_eq!(diagnostics.next(), None, "{}", text);
+        assert_eq!(token.is_error(), !expected.is_empty(), "{}", text);
+    }
+
+    #[test]
+    fn test_string_diagnostics() {
+        use DiagnosticKind::*;
+        use EscapeErrorKind::{InvalidEscapeCharacter, InvalidHexEscape, InvalidUnicodeEscape};
+        check(r#""foo""#, &[]);
+        check(r#""foo\xG0""#, &[(InvalidEscape(InvalidHexEscape), 4, 8)]);
+        check(
+            r#""\q \u{1G}""#,
+            &[
+                (InvalidEscape(InvalidEscapeCharacter), 1, 2),
+                (InvalidEscape(InvalidUnicodeEscape), 4, 8),
+            ],
+        );
+        check(r#""foo"#, &[(UnterminatedStringLiteral, 0, 1)]);
+        check(
+            r#""\xZ"#,
+            &[
+                (InvalidEscape(InvalidHexEscape), 1, 4),
+                (UnterminatedStringLiteral, 0, 1),
+            ],
+        );
+        check(
+            r#"@"a\q""#,
+            &[(InvalidEscape(InvalidEscapeCharacter), 3, 4)],
+        );
+        check(r#"@"a"#, &[(UnterminatedRawIdentifier, 0, 2)]);
+        check("''", &[(EmptyCharacterLiteral, 0, 2)]);
+        check("'a", &[(UnterminatedCharacterLiteral, 0, 1)]);
+        check("'", &[(UnterminatedCharacterLiteral, 0, 1)]);
+        check(r"'\x0'", &[(InvalidEscape(InvalidHexEscape), 1, 4)]);
+    }
+
+    #[test]
+    fn test_escape_value_diagnostics() {
+        use DiagnosticKind::InvalidEscape;
+        use EscapeErrorKind::*;
+        check(
+            r#""\u{110000} \u{D800} \u{0000041}""#,
+            &[
+                (InvalidEscape(CodepointTooLarge), 1, 11),
+                (InvalidEscape(SurrogateCodepoint), 12, 20),
+                (InvalidEscape(UnicodeEscapeTooLong), 21, 32),
+            ],
+        );
+        let text = r#"@"a\x00""#;
+        let (token, _) = lex(text);
+        assert!(!token.is_error());
+        let mut null = diagnostics(token, text, 10);
+        assert_eq!(
+            null.next().map(|d| (d.kind, d.span)),
+            Some((InvalidEscape(NullInIdentifier), Span::new(13, 17)))
+        );
+        assert_eq!(null.next(), None);
+        check(r#""a\x00""#, &[]);
+    }
+
+    #[test]
+    fn test_number_diagnostics() {
+        use DiagnosticKind::*;
+        check("100_000", &[]);
+        check("0b12", &[(InvalidDigit, 3, 4)]);
+        check("0o1289", &[(InvalidDigit, 4, 5), (InvalidDigit, 5, 6)]);
+        check("12a4", &[(InvalidDigit, 2, 3)]);
+        check("1__000", &[(DuplicateUnderscore, 1, 3)]);
+        check("0b1__0", &[(DuplicateUnderscore, 3, 5)]);
+        check("0x1__0", &[(DuplicateUnderscore, 3, 5)]);
+        check("0x", &[(UnterminatedNumber, 1, 2)]);
+        check("1_", &[(UnterminatedNumber, 1, 2)]);
+        check("1.", &[(UnterminatedNumber, 1, 2)]);
+        check("1e", &[(UnterminatedNumber, 1, 2)]);
+        check("1.5e+", &[(UnterminatedNumber, 4, 5)]);
+        check("1.0e1z", &[(InvalidDigit, 5, 6)]);
+        check("1.__5", &[(DuplicateUnderscore, 2, 4)]);
+        check("0x1.fp+64", &[]);
+        check("1p1", &[]);
+        check("1_e", &[(InvalidDigit, 2, 3)]);
+        check("1_p", &[(InvalidDigit, 2, 3)]);
+        check("0x1_p", &[(InvalidDigit, 4, 5)]);
+        check("0x_p1", &[(InvalidDigit, 3, 4)]);
+        check("1__e5", &[(DuplicateUnderscore, 1, 3)]);
+
+        // Every number the lexer flags gets at least one diagnostic.
+        let alphabet = b"01_.epx+";
+        for n in 0..alphabet.len().pow(5) {
+            let mut buf = [b'1'; 6];
+            let mut n = n;
+            for b in &mut buf[1..] {
+                *b = alphabet[n % alphabet.len()];
+                n /= alphabet.len();
+            }
+            for start in [0, 1] {
+                let text = core::str::from_utf8(&buf[start..]).unwrap();
+                let (token, len) = lex(text);
+                let text = &text[..len];
+                if token.is_error()
+                    && matches!(
+                        token,
+                        Token::IntegerLiteral { .. } | Token::FloatLiteral { .. }
+                    )
+                {
+                    assert!(diagnostics(token, text, 0).next().is_some(), "{}", text);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_strict_diagnostics() {
+        use DiagnosticKind::*;
+        let strict = LexOptions {
+            strict: true,
+            ..LexOptions::default()
+        };
+        check_with("0123", strict, &[(LeadingZero, 0, 1)]);
+        check_with("00.5", strict, &[(LeadingZero, 0, 1)]);
+        check_with("0_0", strict, &[(LeadingZero, 0, 1)]);
+        check_with("0_1.5", strict, &[(LeadingZero, 0, 1)]);
+        check("0_0", &[]);
+        check_with("012a", strict, &[(InvalidDigit, 3, 4)]);
+        check("012a", &[(InvalidDigit, 3, 4)]);
+        check_with("0x12", strict, &[]);
+        check_with(r#"@"""#, strict, &[(EmptyRawIdentifier, 0, 3)]);
+        check_with(
+            "// a\u{1}b",
+            strict,
+            &[(
+                InvalidCharacter(InvalidCharacterKind::ControlCharacter, '\u{1}'),
+                0,
+                6,
+            )],
+        );
+        check_with(
+            "\"\u{2028}\"",
+            strict,
+            &[(
+                InvalidCharacter(InvalidCharacterKind::LineSeparator, '\u{2028}'),
+                0,
+                5,
+            )],
+        );
+    }
+
+    #[test]
+    fn test_invalid_diagnostics() {
+        use DiagnosticKind::InvalidCharacter;
+        use InvalidCharacterKind::*;
+        check("$$", &[(InvalidCharacter(UnknownSymbol, '$'), 0, 2)]);
+        check("éü", &[(InvalidCharacter(NonAscii, 'é'), 0, 4)]);
+        check(
+            "\u{1}",
+            &[(InvalidCharacter(ControlCharacter, '\u{1}'), 0, 1)],
+        );
+        check("\\", &[(InvalidCharacter(Backslash, '\\'), 0, 1)]);
+        check("\r", &[(InvalidCharacter(LoneCarriageReturn, '\r'), 0, 1)]);
+        check("foo", &[]);
+        check("+|=", &[]);
+    }
+
+    #[test]
+    fn test_bytes_diagnostics() {
+        use DiagnosticKind::*;
+        use EscapeErrorKind::InvalidEscapeCharacter;
+        let check = |text: &[u8], expected: &[(DiagnosticKind, usize, usize)]| {
+            let (token, len) = lex_bytes(text);
+            assert_eq!(len, text.len(), "{:?}", text);
+            let mut diagnostics = diagnostics_bytes(token, text, 10);
+            for &(kind, start, end) in expected {
+                let diagnostic = diagnostics.next().unwrap();
+                assert_eq!(
+                    (diagnostic.kind, diagnostic.span),
+                    (kind, Span::new(start + 10, end + 10)),
+                    "{:?}",
+                    text
+                );
+            }
+            assert_eq!(diagnostics.next(), None, "{:?}", text);
+        };
+        check(b"\xff", &[(InvalidUtf8, 0, 1)]);
+        check(b"// caf\xe9", &[(InvalidUtf8, 6, 7)]);
+        check(
+            b"// \xff \xe2\x82",
+            &[(InvalidUtf8, 3, 4), (InvalidUtf8, 5, 7)],
+        );
+        check(b"\\\\\xc0\xaf", &[(InvalidUtf8, 2, 3), (InvalidUtf8, 3, 4)]);
+        check(
+            b"\"\\q\xff\"",
+            &[
+                (InvalidEscape(InvalidEscapeCharacter), 1, 2),
+                (InvalidUtf8, 3, 4),
+            ],
+        );
+        check(
+            b"@\"\xff",
+            &[(UnterminatedRawIdentifier, 0, 2), (InvalidUtf8, 2, 3)],
+        );
+        check(b"'\xf0\x9f\x98'", &[(InvalidUtf8, 1, 4)]);
+        check("// é".as_bytes(), &[]);
+    }
+
+    #[test]
+    fn test_display() {
+        use core::fmt::Write;
+
+        struct Buf([u8; 64], usize);
+        impl Write for Buf {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.0[self.1..self.1 + s.len()].copy_from_slice(s.as_bytes());
+                self.1 += s.len();
+                Ok(())
+            }
+        }
+        let check = |kind: DiagnosticKind, expected: &str| {
+            let mut buf = Buf([0; 64], 0);
+            write!(buf, "{}", kind).unwrap();
+            assert_eq!(&buf.0[..buf.1], expected.as_bytes());
+        };
+        let unknown = InvalidCharacterKind::UnknownSymbol;
+        check(
+            DiagnosticKind::InvalidCharacter(InvalidCharacterKind::NonAscii, 'é'),
+            "non-ASCII character U+00E9 'é'",
+        );
+        check(
+            DiagnosticKind::InvalidCharacter(unknown, '$'),
+            "unexpected character U+0024 '$'",
+        );
+        check(
+            DiagnosticKind::InvalidCharacter(InvalidCharacterKind::LoneCarriageReturn, '\r'),
+            "carriage return not followed by a newline U+000D '\\r'",
+        );
+        check(
+            DiagnosticKind::InvalidDigit,
+            "invalid digit in number literal",
+        );
+    }
+}
@@ -0,0 +1,453 @@
+use crate::{Input, Span};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EscapeErrorKind {
+    /// `\` followed by something other than `n`, `r`, `t`, `\`, `'`, `"`, `x` or `u`.
+    InvalidEscapeCharacter,
+    /// `\x` not followed by two hex digits.
+    InvalidHexEscape,
+    /// `\u` not of the form `\u{...}` with at least one hex digit.
+    InvalidUnicodeEscape,
+    /// `\u{...}` with more than six hex digits, even if they are leading zeros.
+    UnicodeEscapeTooLong,
+    /// `\u{...}` above `0x10FFFF`.
+    CodepointTooLarge,
+    /// `\u{...}` in the surrogate range `D800..=DFFF`.
+    SurrogateCodepoint,
+    /// An escape in a raw identifier that produces a zero byte, which
+    /// identifiers cannot contain. Allowed in string and character literals.
+    NullInIdentifier,
+    Unterminated,
+    EmptyCharacterLiteral,
+    MultipleCharacters,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct EscapeError {
+    pub kind: EscapeErrorKind,
+    /// Byte range of the offending escape, relative to the start of the literal.
+    pub span: Span,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Unescaped {
+    /// A `\xNN` escape, which may produce bytes that are not valid UTF-8.
+    Byte(u8),
+    Char(char),
+}
+
+fn is_hex_digit(b: u8) -> bool {
+    b.is_ascii_hexdigit()
+}
+
+pub(crate) const fn hex_value(b: u8) -> u32 {
+    match b {
+        b'0'..=b'9' => (b - b'0') as u32,
+        b'a'..=b'f' => (b - b'a' + 10) as u32,
+        _ => (b - b'A' + 10) as u32,
+    }
+}
+
+/// Checks the value of a well-formed `\xNN` or `\u{...}` escape, which the
+/// state machine leaves to its callers. `value` saturates at `u32::MAX`.
+pub(crate) const fn check_escape_value(
+    is_unicode: bool,
+    digits: u32,
+    value: u32,
+    is_identifier: bool,
+) -> Option<EscapeErrorKind> {
+    if is_unicode && digits > 6 {
+        Some(EscapeErrorKind::UnicodeEscapeTooLong)
+    } else if is_unicode && value > 0x10ffff {
+        Some(EscapeErrorKind::CodepointTooLarge)
+    } else if is_unicode && value >= 0xd800 && value <= 0xdfff {
+        Some(EscapeErrorKind::SurrogateCodepoint)
+    } else if is_identifier && value == 0 {
+        Some(EscapeErrorKind::NullInIdentifier)
+    } else {
+        None
+    }
+}
+
+/// Scans an escape starting at the backslash at `s[start]`, following the same
+/// transitions as the `State::StringEscape*` states. Returns the end of the
+/// escape; characters that `step` reprocesses as literal content are not
+/// included.
+fn scan_escape(
+    s: &[u8],
+    start: usize,
+    is_identifier: bool,
+) -> (usize, Result<Unescaped, EscapeErrorKind>) {
+    let i = start + 1;
+    match s.get(i) {
+        Some(b'n') => (i + 1, Ok(Unescaped::Char('\n'))),
+        Some(b'r') => (i + 1, Ok(Unescaped::Char('\r'))),
+        Some(b't') => (i + 1, Ok(Unescaped::Char('\t'))),
+        Some(b'\\') => (i + 1, Ok(Unescaped::Char('\\'))),
+        Some(b'\'') => (i + 1, Ok(Unescaped::Char('\''))),
+        Some(b'"') => (i + 1, Ok(Unescaped::Char('"'))),
+        Some(b'x') => {
+            let mut i = i + 1;
+            let mut value = 0;
+            let mut valid = true;
+            for _ in 0..2 {
+                match s.get(i) {
+                    Some(&b) if is_hex_digit(b) => value = value * 16 + hex_value(b),
+                    Some(b) if b.is_ascii_alphabetic() => valid = false,
+                    Some(_) | None => return (i, Err(EscapeErrorKind::InvalidHexEscape)),
+                }
+                i += 1;
+            }
+            if !valid {
+                return (i, Err(EscapeErrorKind::InvalidHexEscape));
+            }
+            match check_escape_value(false, 2, value, is_identifier) {
+                Some(kind) => (i, Err(kind)),
+                None => (i, Ok(Unescaped::Byte(value as u8))),
+            }
+        }
+        Some(b'u') => {
+            let mut i = i + 1;
+            if s.get(i) != Some(&b'{') {
+                return (i, Err(EscapeErrorKind::InvalidUnicodeEscape));
+            }
+            i += 1;
+            let mut value: u32 = 0;
+            let digits_start = i;
+            while let Some(&b) = s.get(i).filter(|&&b| is_hex_digit(b)) {
+                value = value.saturating_mul(16).saturating_add(hex_value(b));
+                i += 1;
+            }
+            if i == digits_start || s.get(i) != Some(&b'}') {
+                return (i, Err(EscapeErrorKind::InvalidUnicodeEscape));
+            }
+            let digits = (i - digits_start) as u32;
+            match check_escape_value(true, digits, value, is_identifier) {
+                Some(kind) => (i + 1, Err(kind)),
+                None => (
+                    i + 1,
+                    Ok(Unescaped::Char(core::char::from_u32(value).unwrap())),
+                ),
+            }
+        }
+        Some(_) | None => (i, Err(EscapeErrorKind::InvalidEscapeCharacter)),
+    }
+}
+
+/// Iterator over the contents of a string or character literal, yielding each
+/// character or escape and reporting every bad escape along the way.
+#[derive(Debug, Clone)]
+pub struct Unescape<'a> {
+    text: &'a [u8],
+    pos: usize,
+    end: usize,
+    is_unterminated: bool,
+    is_identifier: bool,
+}
+
+impl<'a> Unescape<'a> {
+    /// `text` is normally valid UTF-8; invalid sequences in it, which only
+    /// [`crate::diagnostics_bytes`] passes, decode as U+FFFD.
+    pub(crate) fn new(text: &'a [u8], prefix: &[u8], quote: u8) -> Unescape<'a> {
+        let start = match text.starts_with(prefix) {
+            true => prefix.len(),
+            false => 0,
+        };
+        // The literal ends at the first quote that is not escaped, as in the
+        // lexer; `"abc\"` is unterminated.
+        let (mut end, mut is_unterminated) = (text.len(), true);
+        let mut i = start;
+        while let Some(&b) = text.get(i) {
+            match b {
+                b'\\' => i += 2,
+                _ if b == quote => {
+                    end = i;
+                    is_unterminated = false;
+                    break;
+                }
+                _ => i += 1,
+            }
+        }
+        Unescape {
+            text,
+            pos: start,
+            end,
+            is_unterminated,
+            is_identifier: prefix == b"@\"",
+        }
+    }
+}
+
+impl<'a> Iterator for Unescape<'a> {
+    type Item = Result<Unescaped, EscapeError>;
+
+    fn next(&mut self) -> Option<Result<Unescaped, EscapeError>> {
+        if self.pos >= self.end {
+            if self.is_unterminated {
+                self.is_unterminated = false;
+                return Some(Err(EscapeError {
+                    kind: EscapeErrorKind::Unterminated,
+                    span: Span::new(self.end, self.end),
+                }));
+            }
+            return None;
+        }
+        let start = self.pos;
+        let (c, len, _) = self.text[..self.end].decode(start).unwrap();
+        if c != '\\' {
+            self.pos += len;
+            return Some(Ok(Unescaped::Char(c)));
+        }
+        let (end, result) = scan_escape(&self.text[..self.end], start, self.is_identifier);
+        self.pos = end;
+        Some(result.map_err(|kind| EscapeError {
+            kind,
+            span: Span::new(start, end),
+        }))
+    }
+}
+
+impl<'a> core::iter::FusedIterator for Unescape<'a> {}
+
+/// Unescapes the text of a string literal token, including its quotes.
+pub fn unescape_string_literal(text: &str) -> Unescape<'_> {
+    Unescape::new(text.as_bytes(), b"\"", b'"')
+}
+
+/// Unescapes the text of a raw identifier token, including its `@"` and `"`.
+pub fn unescape_raw_identifier(text: &str) -> Unescape<'_> {
+    Unescape::new(text.as_bytes(), b"@\"", b'"')
+}
+
+/// Unescapes the text of a character literal token, including its quotes.
+pub fn unescape_char_literal(text: &str) -> Unescape<'_> {
+    Unescape::new(text.as_bytes(), b"'", b'\'')
+}
+
+/// The bytes a valid string literal evaluates to.
+#[derive(Debug, Clone)]
+pub struct StringLiteralBytes<'a> {
+    unescape: Unescape<'a>,
+    buf: [u8; 4],
+    buf_pos: usize,
+    buf_len: usize,
+}
+
+impl<'a> Iterator for StringLiteralBytes<'a> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.buf_pos < self.buf_len {
+            self.buf_pos += 1;
+            return Some(self.buf[self.buf_pos - 1]);
+        }
+        match self.unescape.next()? {
+            Ok(Unescaped::Byte(b)) => Some(b),
+            Ok(Unescaped::Char(c)) => {
+                self.buf_len = c.encode_utf8(&mut self.buf).len();
+                self.buf_pos = 1;
+                Some(self.buf[0])
+            }
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+impl<'a> core::iter::FusedIterator for StringLiteralBytes<'a> {}
+
+fn decode_bytes(unescape: Unescape<'_>) -> Result<StringLiteralBytes<'_>, EscapeError> {
+    if let Some(Err(err)) = unescape.clone().find(Result::is_err) {
+        return Err(err);
+    }
+    Ok(StringLiteralBytes {
+        unescape,
+        buf: [0; 4],
+        buf_pos: 0,
+        buf_len: 0,
+    })
+}
+
+/// Decodes a string literal token into its bytes, failing on the first bad
+/// escape. Use [`unescape_string_literal`] to see every error.
+pub fn decode_string_literal(text: &str) -> Result<StringLiteralBytes<'_>, EscapeError> {
+    decode_bytes(unescape_string_literal(text))
+}
+
+pub fn decode_raw_identifier(text: &str) -> Result<StringLiteralBytes<'_>, EscapeError> {
+    decode_bytes(unescape_raw_identifier(text))
+}
+
+/// Decodes a character literal token into its code point. `\xNN` escapes
+/// produce the byte value.
+pub fn decode_char_literal(text: &str) -> Result<u32, EscapeError> {
+    let mut unescape = unescape_char_literal(text);
+    let value = match unescape.next() {
+        None => {
+            return Err(EscapeError {
+                kind: EscapeErrorKind::EmptyCharacterLiteral,
+                span: Span::new(0, text.len()),
+            })
+        }
+        Some(Err(err)) => return Err(err),
+        Some(Ok(Unescaped::Byte(b))) => u32::from(b),
+        Some(Ok(Unescaped::Char(c))) => u32::from(c),
+    };
+    let rest = unescape.pos;
+    match unescape.next() {
+        None => Ok(value),
+        Some(Err(err)) if err.kind == EscapeErrorKind::Unterminated => Err(err),
+        Some(_) => Err(EscapeError {
+            kind: EscapeErrorKind::MultipleCharacters,
+            span: Span::new(rest, unescape.end),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{lex, Token};
+
+    fn bytes_eq(text: &str, expected: &[u8]) -> bool {
+        let mut bytes = decode_string_literal(text).unwrap();
+        expected.iter().all(|&b| bytes.next() == Some(b)) && bytes.next().is_none()
+    }
+
+    fn err(kind: EscapeErrorKind, start: usize, end: usize) -> EscapeError {
+        EscapeError {
+            kind,
+            span: Span::new(start, end),
+        }
+    }
+
+    #[test]
+    fn test_decode_string_literal() {
+        use EscapeErrorKind::*;
+        assert!(bytes_eq(r#""""#, b""));
+        assert!(bytes_eq(r#""foo bar""#, b"foo bar"));
+        assert!(bytes_eq(r#""\\\"\'\r\n\t""#, b"\\\"'\r\n\t"));
+        assert!(bytes_eq(r#""\x00\xFF""#, b"\x00\xff"));
+        assert!(bytes_eq(r#""\u{1F}\u{e9}""#, "\u{1f}\u{e9}".as_bytes()));
+        assert!(bytes_eq(r#""é\u{1F600}""#, "é\u{1F600}".as_bytes()));
+        assert!(bytes_eq(r#""std.zig""#, b"std.zig"));
+        let e = |text| decode_string_literal(text).err();
+        assert_eq!(e(r#""foo bar\x0""#), Some(err(InvalidHexEscape, 8, 11)));
+        assert_eq!(e(r#""foo bar\xG0""#), Some(err(InvalidHexEscape, 8, 12)));
+        assert_eq!(e(r#""\q""#), Some(err(InvalidEscapeCharacter, 1, 2)));
+        assert_eq!(e(r#""\u""#), Some(err(InvalidUnicodeEscape, 1, 3)));
+        assert_eq!(e(r#""\u{}""#), Some(err(InvalidUnicodeEscape, 1, 4)));
+        assert_eq!(e(r#""\u{1G}""#), Some(err(InvalidUnicodeEscape, 1, 5)));
+        assert_eq!(e(r#""\u{D800}""#), Some(err(SurrogateCodepoint, 1, 9)));
+        assert_eq!(e(r#""\u{dfff}""#), Some(err(SurrogateCodepoint, 1, 9)));
+        assert_eq!(e(r#""\u{110000}""#), Some(err(CodepointTooLarge, 1, 11)));
+        assert_eq!(
+            e(r#""\u{FFFFFFFFF}""#),
+            Some(err(UnicodeEscapeTooLong, 1, 14))
+        );
+        assert_eq!(
+            e(r#""\u{0000041}""#),
+            Some(err(UnicodeEscapeTooLong, 1, 12))
+        );
+        assert!(bytes_eq(
+            r#""\u{000041}\u{10FFFF}""#,
+            "A\u{10FFFF}".as_bytes()
+        ));
+        assert!(bytes_eq(r#""\x00\u{0}""#, b"\0\0"));
+        assert_eq!(e(r#""foo"#), Some(err(Unterminated, 4, 4)));
+        assert_eq!(e(r#""abc\""#), Some(err(Unterminated, 6, 6)));
+        assert_eq!(e(r#""\\\""#), Some(err(Unterminated, 5, 5)));
+    }
+
+    #[test]
+    fn test_unescape_reports_every_error() {
+        use EscapeErrorKind::*;
+        let mut errors = unescape_string_literal(r#""\q a \xZZ \u{1""#).filter_map(Result::err);
+        assert_eq!(errors.next(), Some(err(InvalidEscapeCharacter, 1, 2)));
+        assert_eq!(errors.next(), Some(err(InvalidHexEscape, 6, 10)));
+        assert_eq!(errors.next(), Some(err(InvalidUnicodeEscape, 11, 15)));
+        assert_eq!(errors.next(), None);
+    }
+
+    #[test]
+    fn test_decode_raw_identifier() {
+        use EscapeErrorKind::NullInIdentifier;
+        let mut bytes = decode_raw_identifier(r#"@"foo\x20bar""#).unwrap();
+        assert!(b"foo bar".iter().all(|&b| bytes.next() == Some(b)));
+        assert_eq!(bytes.next(), None);
+        let e = |text| decode_raw_identifier(text).err();
+        assert_eq!(e(r#"@"a\x00""#), Some(err(NullInIdentifier, 3, 7)));
+        assert_eq!(e(r#"@"\u{0}""#), Some(err(NullInIdentifier, 2, 7)));
+        assert!(e(r#"@"\xff\u{1}""#).is_none());
+    }
+
+    #[test]
+    fn test_decode_char_literal() {
+        use EscapeErrorKind::*;
+        assert_eq!(decode_char_literal("'a'"), Ok(0x61));
+        assert_eq!(decode_char_literal("'é'"), Ok(0xe9));
+        assert_eq!(decode_char_literal(r"'\n'"), Ok(0x0a));
+        assert_eq!(decode_char_literal(r"'\xFF'"), Ok(0xff));
+        assert_eq!(decode_char_literal(r"'\u{1F600}'"), Ok(0x1f600));
+        assert_eq!(
+            decode_char_literal("''"),
+            Err(err(EmptyCharacterLiteral, 0, 2))
+        );
+        assert_eq!(
+            decode_char_literal("'ab'"),
+            Err(err(MultipleCharacters, 2, 3))
+        );
+        assert_eq!(decode_char_literal("'a"), Err(err(Unterminated, 2, 2)));
+        assert_eq!(decode_char_literal(r"'\'"), Err(err(Unterminated, 3, 3)));
+        assert_eq!(
+            decode_char_literal(r"'\x0'"),
+            Err(err(InvalidHexEscape, 1, 4))
+        );
+    }
+
+    #[test]
+    fn test_agrees_with_lexer() {
+        for &text in &[
+            r#""foo bar\x00""#,
+            r#""foo bar\x0""#,
+            r#""foo bar\xFG""#,
+            r#""foo bar\xGG""#,
+            r#""foo bar\u{""#,
+            r#""foo bar\u{1""#,
+            r#""foo bar\u{1G}""#,
+            r#""foo bar\u{1F}""#,
+            r#""\q\"""#,
+            r#""\\""#,
+            r#""\u{10FFFF}\u{000000}""#,
+            r#""\u{110000}""#,
+            r#""\u{D800}""#,
+            r#""\u{0000000041}""#,
+            r#""\x00""#,
+            r#""abc\""#,
+            r#""\\\""#,
+            r#""\q"#,
+        ] {
+            let (token, len) = lex(text);
+            assert_eq!(len, text.len());
+            let (is_unterminated, has_invalid_escape) = match token {
+                Token::StringLiteral {
+                    is_unterminated,
+                    has_invalid_escape,
+                } => (is_unterminated, has_invalid_escape),
+                _ => unreachable!(),
+            };
+            let errors = || unescape_string_literal(text).filter_map(Result::err);
+            assert_eq!(
+                errors().any(|err| err.kind != EscapeErrorKind::Unterminated),
+                has_invalid_escape,
+                "{}",
+                text
+            );
+            assert_eq!(
+                errors().any(|err| err.kind == EscapeErrorKind::Unterminated),
+                is_unterminated,
+                "{}",
+                text
+            );
+        }
+    }
+}
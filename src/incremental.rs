@@ -0,0 +1,178 @@
+use crate::{lex, Span, Token};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Replacement of `range` in the old source with `new_text`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct TextEdit<'a> {
+    pub range: Span,
+    pub new_text: &'a str,
+}
+
+impl<'a> TextEdit<'a> {
+    pub fn apply(&self, source: &str) -> String {
+        let mut result =
+            String::with_capacity(source.len() - self.range.len() + self.new_text.len());
+        result.push_str(&source[..self.range.start]);
+        result.push_str(self.new_text);
+        result.push_str(&source[self.range.end..]);
+        result
+    }
+}
+
+/// Updates `tokens`, the result of lexing the source before `edit`, to match
+/// `new_source`, the source after it. Returns the indices in `tokens` that
+/// were replaced; everything outside that range is unchanged apart from
+/// having its spans shifted.
+///
+/// Relexing starts at the beginning of the line before the edit, which is
+/// always a safe place to start from since only whitespace tokens span a
+/// newline, or at the whitespace token ending there, which the edit can
+/// extend by completing a `\r\n`. It stops as soon as a new token ends on an old token boundary
+/// past the edit, after which the old tokens are known to be still valid.
+pub fn relex(
+    tokens: &mut Vec<(Token, Span)>,
+    new_source: &str,
+    edit: &TextEdit<'_>,
+) -> Range<usize> {
+    let old_len = tokens.last().map_or(0, |&(_, span)| span.end);
+    let new_len = old_len - edit.range.len() + edit.new_text.len();
+    debug_assert_eq!(new_len, new_source.len());
+    let edit_end = edit.range.start + edit.new_text.len();
+
+    let line_start = match edit.range.start.checked_sub(1) {
+        Some(before) => new_source.as_bytes()[..before]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1),
+        None => 0,
+    };
+    let mut first = tokens.partition_point(|&(_, span)| span.end <= line_start);
+    if let Some((Token::Whitespace, _)) = first.checked_sub(1).map(|i| tokens[i]) {
+        first -= 1;
+    }
+    let mut pos = tokens.get(first).map_or(old_len, |&(_, span)| span.start);
+
+    let mut new_tokens = Vec::new();
+    let mut last = tokens.len();
+    while pos < new_len {
+        let (token, len) = lex(&new_source[pos..]);
+        new_tokens.push((token, Span::new(pos, pos + len)));
+        pos += len;
+        if pos >= edit_end {
+            let old_pos = pos + edit.range.len() - edit.new_text.len();
+            let next = tokens.partition_point(|&(_, span)| span.start < old_pos);
+            if tokens.get(next).map(|&(_, span)| span.start) == Some(old_pos) {
+                last = next;
+                break;
+            }
+        }
+    }
+
+    for (_, span) in &mut tokens[last..] {
+        *span = Span::new(
+            span.start + edit.new_text.len() - edit.range.len(),
+            span.end + edit.new_text.len() - edit.range.len(),
+        );
+    }
+    let count = new_tokens.len();
+    tokens.splice(first..last, new_tokens);
+    first..first + count
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Lexer;
+
+    const SOURCE: &str = "const std = @import(\"std\");\r\n\n/// Adds.\nfn add(a: u32, b: u32) u32 {\n    return a +| b; // sat\n}\n\ntest {\n    const x = 0x1.fp3;\n    _ = 1..2;\n    const s = \\\\line\n    ;\n}\n";
+
+    const SNIPPETS: &[&str] = &[
+        "", " ", "\n", "\r\n", "//", "/", "\"", "'", "\\\\", ".", "..", "1", "0x", "e", "_", "fn",
+        "x", "@\"", "\\", "é", "|=", "<<", "$", "\t\n  ", "\r", "\r\u{1}",
+    ];
+
+    fn check(source: &str, edit: TextEdit<'_>) {
+        let mut tokens: Vec<_> = Lexer::new(source).collect();
+        let before = tokens.clone();
+        let new_source = edit.apply(source);
+        let changed = relex(&mut tokens, &new_source, &edit);
+        let expected: Vec<_> = Lexer::new(&new_source).collect();
+        assert_eq!(tokens, expected, "{:?} -> {:?}", source, new_source);
+        assert_eq!(tokens[..changed.start], before[..changed.start]);
+    }
+
+    #[test]
+    fn test_relex_simple() {
+        let source = "const a = 1;\nconst b = 2;\nconst c = 3;\n";
+        let mut tokens: Vec<_> = Lexer::new(source).collect();
+        let edit = TextEdit {
+            range: Span::new(23, 24),
+            new_text: "42",
+        };
+        let new_source = edit.apply(source);
+        let changed = relex(&mut tokens, &new_source, &edit);
+        let expected: Vec<_> = Lexer::new(&new_source).collect();
+        assert_eq!(tokens, expected);
+        assert!(changed.len() < 10, "{:?}", changed);
+    }
+
+    #[test]
+    fn test_relex_matches_full_lex() {
+        let mut seed: u32 = 0x1234_5678;
+        let mut next = |n: usize| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 8) as usize % n
+        };
+        let boundaries: Vec<usize> = (0..=SOURCE.len())
+            .filter(|&i| SOURCE.is_char_boundary(i))
+            .collect();
+        for _ in 0..2000 {
+            let a = boundaries[next(boundaries.len())];
+            let b = boundaries[next(boundaries.len())];
+            let range = Span::new(a.min(b), a.max(b).min(a.min(b) + 4));
+            let range = match SOURCE.is_char_boundary(range.end) {
+                true => range,
+                false => Span::new(range.start, range.start),
+            };
+            let new_text = SNIPPETS[next(SNIPPETS.len())];
+            check(SOURCE, TextEdit { range, new_text });
+        }
+        check(
+            "",
+            TextEdit {
+                range: Span::new(0, 0),
+                new_text: "fn",
+            },
+        );
+        check(
+            "fn",
+            TextEdit {
+                range: Span::new(0, 2),
+                new_text: "",
+            },
+        );
+        check(
+            "a\n b",
+            TextEdit {
+                range: Span::new(2, 2),
+                new_text: " ",
+            },
+        );
+        check(
+            "1\n..",
+            TextEdit {
+                range: Span::new(1, 2),
+                new_text: "",
+            },
+        );
+        check(
+            "\r\n\r\u{1}Pxfp",
+            TextEdit {
+                range: Span::new(3, 5),
+                new_text: "\n",
+            },
+        );
+    }
+}
@@ -0,0 +1,898 @@
+use crate::{Keyword, Token};
+use core::fmt;
+use core::str::FromStr;
+
+/// The kind of a [`Token`], without its payload.
+///
+/// Keywords get a kind each. The `u8` values are stable: new kinds are only
+/// ever added at the end.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[repr(u8)]
+pub enum TokenKind {
+    Invalid,
+    InvalidUtf8,
+    Whitespace,
+    Comment,
+    DocComment,
+    ContainerDocComment,
+    Builtin,
+    Identifier,
+    KeywordAddrspace,
+    KeywordAlign,
+    KeywordAllowzero,
+    KeywordAnd,
+    KeywordAnyframe,
+    KeywordAnytype,
+    KeywordAsm,
+    KeywordAsync,
+    KeywordAwait,
+    KeywordBreak,
+    KeywordCallconv,
+    KeywordCatch,
+    KeywordComptime,
+    KeywordConst,
+    KeywordContinue,
+    KeywordDefer,
+    KeywordElse,
+    KeywordEnum,
+    KeywordErrdefer,
+    KeywordError,
+    KeywordExport,
+    KeywordExtern,
+    KeywordFn,
+    KeywordFor,
+    KeywordIf,
+    KeywordInline,
+    KeywordLinksection,
+    KeywordNoalias,
+    KeywordNoinline,
+    KeywordNosuspend,
+    KeywordOpaque,
+    KeywordOr,
+    KeywordOrelse,
+    KeywordPacked,
+    KeywordPub,
+    KeywordResume,
+    KeywordReturn,
+    KeywordStruct,
+    KeywordSuspend,
+    KeywordSwitch,
+    KeywordTest,
+    KeywordThreadlocal,
+    KeywordTry,
+    KeywordUnion,
+    KeywordUnreachable,
+    KeywordUsingnamespace,
+    KeywordVar,
+    KeywordVolatile,
+    KeywordWhile,
+    RawIdentifier,
+    IntegerLiteral,
+    FloatLiteral,
+    StringLiteral,
+    CharacterLiteral,
+    MultilineStringLiteralLine,
+    Bang,              // !
+    BangEqual,         // !=
+    Percent,           // %
+    PercentEqual,      // %=
+    And,               // &
+    And2,              // &&
+    AndEqual,          // &=
+    LParen,            // (
+    RParen,            // )
+    Star,              // *
+    Star2,             // **
+    StarEqual,         // *=
+    StarPercent,       // *%
+    StarPercentEqual,  // *%=
+    StarOr,            // *|
+    StarOrEqual,       // *|=
+    Plus,              // +
+    Plus2,             // ++
+    PlusEqual,         // +=
+    PlusPercent,       // +%
+    PlusPercentEqual,  // +%=
+    PlusOr,            // +|
+    PlusOrEqual,       // +|=
+    Comma,             // ,
+    Minus,             // -
+    MinusEqual,        // -=
+    MinusPercent,      // -%
+    MinusPercentEqual, // -%=
+    MinusOr,           // -|
+    MinusOrEqual,      // -|=
+    Dot,               // .
+    Dot2,              // ..
+    Dot3,              // ...
+    DotStar,           // .*
+    Slash,             // /
+    SlashEqual,        // /=
+    Colon,             // :
+    Semicolon,         // ;
+    LAngle,            // <
+    LAngleEqual,       // <=
+    LAngle2,           // <<
+    LAngle2Equal,      // <<=
+    LAngle2Or,         // <<|
+    LAngle2OrEqual,    // <<|=
+    Equal,             // =
+    Equal2,            // ==
+    EqualRAngle,       // =>
+    RAngle,            // >
+    RAngleEqual,       // >=
+    RAngle2,           // >>
+    RAngle2Equal,      // >>=
+    Question,          // ?
+    At,                // @
+    LBracket,          // [
+    RBracket,          // ]
+    Caret,             // ^
+    CaretEqual,        // ^=
+    LBrace,            // {
+    Or,                // |
+    Or2,               // ||
+    OrEqual,           // |=
+    RBrace,            // }
+    Tilde,             // ~
+    Eof,
+    Newline,
+}
+
+impl TokenKind {
+    /// Every kind, ordered by its `u8` value.
+    pub const ALL: [TokenKind; 128] = [
+        TokenKind::Invalid,
+        TokenKind::InvalidUtf8,
+        TokenKind::Whitespace,
+        TokenKind::Comment,
+        TokenKind::DocComment,
+        TokenKind::ContainerDocComment,
+        TokenKind::Builtin,
+        TokenKind::Identifier,
+        TokenKind::KeywordAddrspace,
+        TokenKind::KeywordAlign,
+        TokenKind::KeywordAllowzero,
+        TokenKind::KeywordAnd,
+        TokenKind::KeywordAnyframe,
+        TokenKind::KeywordAnytype,
+        TokenKind::KeywordAsm,
+        TokenKind::KeywordAsync,
+        TokenKind::KeywordAwait,
+        TokenKind::KeywordBreak,
+        TokenKind::KeywordCallconv,
+        TokenKind::KeywordCatch,
+        TokenKind::KeywordComptime,
+        TokenKind::KeywordConst,
+        TokenKind::KeywordContinue,
+        TokenKind::KeywordDefer,
+        TokenKind::KeywordElse,
+        TokenKind::KeywordEnum,
+        TokenKind::KeywordErrdefer,
+        TokenKind::KeywordError,
+        TokenKind::KeywordExport,
+        TokenKind::KeywordExtern,
+        TokenKind::KeywordFn,
+        TokenKind::KeywordFor,
+        TokenKind::KeywordIf,
+        TokenKind::KeywordInline,
+        TokenKind::KeywordLinksection,
+        TokenKind::KeywordNoalias,
+        TokenKind::KeywordNoinline,
+        TokenKind::KeywordNosuspend,
+        TokenKind::KeywordOpaque,
+        TokenKind::KeywordOr,
+        TokenKind::KeywordOrelse,
+        TokenKind::KeywordPacked,
+        TokenKind::KeywordPub,
+        TokenKind::KeywordResume,
+        TokenKind::KeywordReturn,
+        TokenKind::KeywordStruct,
+        TokenKind::KeywordSuspend,
+        TokenKind::KeywordSwitch,
+        TokenKind::KeywordTest,
+        TokenKind::KeywordThreadlocal,
+        TokenKind::KeywordTry,
+        TokenKind::KeywordUnion,
+        TokenKind::KeywordUnreachable,
+        TokenKind::KeywordUsingnamespace,
+        TokenKind::KeywordVar,
+        TokenKind::KeywordVolatile,
+        TokenKind::KeywordWhile,
+        TokenKind::RawIdentifier,
+        TokenKind::IntegerLiteral,
+        TokenKind::FloatLiteral,
+        TokenKind::StringLiteral,
+        TokenKind::CharacterLiteral,
+        TokenKind::MultilineStringLiteralLine,
+        TokenKind::Bang,
+        TokenKind::BangEqual,
+        TokenKind::Percent,
+        TokenKind::PercentEqual,
+        TokenKind::And,
+        TokenKind::And2,
+        TokenKind::AndEqual,
+        TokenKind::LParen,
+        TokenKind::RParen,
+        TokenKind::Star,
+        TokenKind::Star2,
+        TokenKind::StarEqual,
+        TokenKind::StarPercent,
+        TokenKind::StarPercentEqual,
+        TokenKind::StarOr,
+        TokenKind::StarOrEqual,
+        TokenKind::Plus,
+        TokenKind::Plus2,
+        TokenKind::PlusEqual,
+        TokenKind::PlusPercent,
+        TokenKind::PlusPercentEqual,
+        TokenKind::PlusOr,
+        TokenKind::PlusOrEqual,
+        TokenKind::Comma,
+        TokenKind::Minus,
+        TokenKind::MinusEqual,
+        TokenKind::MinusPercent,
+        TokenKind::MinusPercentEqual,
+        TokenKind::MinusOr,
+        TokenKind::MinusOrEqual,
+        TokenKind::Dot,
+        TokenKind::Dot2,
+        TokenKind::Dot3,
+        TokenKind::DotStar,
+        TokenKind::Slash,
+        TokenKind::SlashEqual,
+        TokenKind::Colon,
+        TokenKind::Semicolon,
+        TokenKind::LAngle,
+        TokenKind::LAngleEqual,
+        TokenKind::LAngle2,
+        TokenKind::LAngle2Equal,
+        TokenKind::LAngle2Or,
+        TokenKind::LAngle2OrEqual,
+        TokenKind::Equal,
+        TokenKind::Equal2,
+        TokenKind::EqualRAngle,
+        TokenKind::RAngle,
+        TokenKind::RAngleEqual,
+        TokenKind::RAngle2,
+        TokenKind::RAngle2Equal,
+        TokenKind::Question,
+        TokenKind::At,
+        TokenKind::LBracket,
+        TokenKind::RBracket,
+        TokenKind::Caret,
+        TokenKind::CaretEqual,
+        TokenKind::LBrace,
+        TokenKind::Or,
+        TokenKind::Or2,
+        TokenKind::OrEqual,
+        TokenKind::RBrace,
+        TokenKind::Tilde,
+        TokenKind::Eof,
+        TokenKind::Newline,
+    ];
+
+    pub const fn from_u8(value: u8) -> Option<TokenKind> {
+        if (value as usize) < TokenKind::ALL.len() {
+            Some(TokenKind::ALL[value as usize])
+        } else {
+            None
+        }
+    }
+
+    /// The keyword this kind stands for, if any.
+    pub fn keyword(self) -> Option<Keyword> {
+        Some(match self {
+            TokenKind::KeywordAddrspace => Keyword::Addrspace,
+            TokenKind::KeywordAlign => Keyword::Align,
+            TokenKind::KeywordAllowzero => Keyword::Allowzero,
+            TokenKind::KeywordAnd => Keyword::And,
+            TokenKind::KeywordAnyframe => Keyword::Anyframe,
+            TokenKind::KeywordAnytype => Keyword::Anytype,
+            TokenKind::KeywordAsm => Keyword::Asm,
+            TokenKind::KeywordAsync => Keyword::Async,
+            TokenKind::KeywordAwait => Keyword::Await,
+            TokenKind::KeywordBreak => Keyword::Break,
+            TokenKind::KeywordCallconv => Keyword::Callconv,
+            TokenKind::KeywordCatch => Keyword::Catch,
+            TokenKind::KeywordComptime => Keyword::Comptime,
+            TokenKind::KeywordConst => Keyword::Const,
+            TokenKind::KeywordContinue => Keyword::Continue,
+            TokenKind::KeywordDefer => Keyword::Defer,
+            TokenKind::KeywordElse => Keyword::Else,
+            TokenKind::KeywordEnum => Keyword::Enum,
+            TokenKind::KeywordErrdefer => Keyword::Errdefer,
+            TokenKind::KeywordError => Keyword::Error,
+            TokenKind::KeywordExport => Keyword::Export,
+            TokenKind::KeywordExtern => Keyword::Extern,
+            TokenKind::KeywordFn => Keyword::Fn,
+            TokenKind::KeywordFor => Keyword::For,
+            TokenKind::KeywordIf => Keyword::If,
+            TokenKind::KeywordInline => Keyword::Inline,
+            TokenKind::KeywordLinksection => Keyword::Linksection,
+            TokenKind::KeywordNoalias => Keyword::Noalias,
+            TokenKind::KeywordNoinline => Keyword::Noinline,
+            TokenKind::KeywordNosuspend => Keyword::Nosuspend,
+            TokenKind::KeywordOpaque => Keyword::Opaque,
+            TokenKind::KeywordOr => Keyword::Or,
+            TokenKind::KeywordOrelse => Keyword::Orelse,
+            TokenKind::KeywordPacked => Keyword::Packed,
+            TokenKind::KeywordPub => Keyword::Pub,
+            TokenKind::KeywordResume => Keyword::Resume,
+            TokenKind::KeywordReturn => Keyword::Return,
+            TokenKind::KeywordStruct => Keyword::Struct,
+            TokenKind::KeywordSuspend => Keyword::Suspend,
+            TokenKind::KeywordSwitch => Keyword::Switch,
+            TokenKind::KeywordTest => Keyword::Test,
+            TokenKind::KeywordThreadlocal => Keyword::Threadlocal,
+            TokenKind::KeywordTry => Keyword::Try,
+            TokenKind::KeywordUnion => Keyword::Union,
+            TokenKind::KeywordUnreachable => Keyword::Unreachable,
+            TokenKind::KeywordUsingnamespace => Keyword::Usingnamespace,
+            TokenKind::KeywordVar => Keyword::Var,
+            TokenKind::KeywordVolatile => Keyword::Volatile,
+            TokenKind::KeywordWhile => Keyword::While,
+            _ => return None,
+        })
+    }
+
+    /// The text every token of this kind has, for punctuation and keywords.
+    pub fn fixed_text(self) -> Option<&'static str> {
+        if let Some(keyword) = self.keyword() {
+            return Some(keyword.as_str());
+        }
+        Some(match self {
+            TokenKind::Bang => "!",
+            TokenKind::BangEqual => "!=",
+            TokenKind::Percent => "%",
+            TokenKind::PercentEqual => "%=",
+            TokenKind::And => "&",
+            TokenKind::And2 => "&&",
+            TokenKind::AndEqual => "&=",
+            TokenKind::LParen => "(",
+            TokenKind::RParen => ")",
+            TokenKind::Star => "*",
+            TokenKind::Star2 => "**",
+            TokenKind::StarEqual => "*=",
+            TokenKind::StarPercent => "*%",
+            TokenKind::StarPercentEqual => "*%=",
+            TokenKind::StarOr => "*|",
+            TokenKind::StarOrEqual => "*|=",
+            TokenKind::Plus => "+",
+            TokenKind::Plus2 => "++",
+            TokenKind::PlusEqual => "+=",
+            TokenKind::PlusPercent => "+%",
+            TokenKind::PlusPercentEqual => "+%=",
+            TokenKind::PlusOr => "+|",
+            TokenKind::PlusOrEqual => "+|=",
+            TokenKind::Comma => ",",
+            TokenKind::Minus => "-",
+            TokenKind::MinusEqual => "-=",
+            TokenKind::MinusPercent => "-%",
+            TokenKind::MinusPercentEqual => "-%=",
+            TokenKind::MinusOr => "-|",
+            TokenKind::MinusOrEqual => "-|=",
+            TokenKind::Dot => ".",
+            TokenKind::Dot2 => "..",
+            TokenKind::Dot3 => "...",
+            TokenKind::DotStar => ".*",
+            TokenKind::Slash => "/",
+            TokenKind::SlashEqual => "/=",
+            TokenKind::Colon => ":",
+            TokenKind::Semicolon => ";",
+            TokenKind::LAngle => "<",
+            TokenKind::LAngleEqual => "<=",
+            TokenKind::LAngle2 => "<<",
+            TokenKind::LAngle2Equal => "<<=",
+            TokenKind::LAngle2Or => "<<|",
+            TokenKind::LAngle2OrEqual => "<<|=",
+            TokenKind::Equal => "=",
+            TokenKind::Equal2 => "==",
+            TokenKind::EqualRAngle => "=>",
+            TokenKind::RAngle => ">",
+            TokenKind::RAngleEqual => ">=",
+            TokenKind::RAngle2 => ">>",
+            TokenKind::RAngle2Equal => ">>=",
+            TokenKind::Question => "?",
+            TokenKind::At => "@",
+            TokenKind::LBracket => "[",
+            TokenKind::RBracket => "]",
+            TokenKind::Caret => "^",
+            TokenKind::CaretEqual => "^=",
+            TokenKind::LBrace => "{",
+            TokenKind::Or => "|",
+            TokenKind::Or2 => "||",
+            TokenKind::OrEqual => "|=",
+            TokenKind::RBrace => "}",
+            TokenKind::Tilde => "~",
+            _ => return None,
+        })
+    }
+
+    /// The name of the matching `std.zig.Token.Tag`, if Zig's tokenizer has
+    /// one. Whitespace and plain comments are skipped by Zig, and `&&` and a
+    /// lone `@` are not tokens there.
+    pub fn zig_tag(self) -> Option<&'static str> {
+        Some(match self {
+            TokenKind::Invalid => "invalid",
+            TokenKind::InvalidUtf8 => "invalid",
+            TokenKind::DocComment => "doc_comment",
+            TokenKind::ContainerDocComment => "container_doc_comment",
+            TokenKind::Builtin => "builtin",
+            TokenKind::Identifier => "identifier",
+            TokenKind::KeywordAddrspace => "keyword_addrspace",
+            TokenKind::KeywordAlign => "keyword_align",
+            TokenKind::KeywordAllowzero => "keyword_allowzero",
+            TokenKind::KeywordAnd => "keyword_and",
+            TokenKind::KeywordAnyframe => "keyword_anyframe",
+            TokenKind::KeywordAnytype => "keyword_anytype",
+            TokenKind::KeywordAsm => "keyword_asm",
+            TokenKind::KeywordAsync => "keyword_async",
+            TokenKind::KeywordAwait => "keyword_await",
+            TokenKind::KeywordBreak => "keyword_break",
+            TokenKind::KeywordCallconv => "keyword_callconv",
+            TokenKind::KeywordCatch => "keyword_catch",
+            TokenKind::KeywordComptime => "keyword_comptime",
+            TokenKind::KeywordConst => "keyword_const",
+            TokenKind::KeywordContinue => "keyword_continue",
+            TokenKind::KeywordDefer => "keyword_defer",
+            TokenKind::KeywordElse => "keyword_else",
+            TokenKind::KeywordEnum => "keyword_enum",
+            TokenKind::KeywordErrdefer => "keyword_errdefer",
+            TokenKind::KeywordError => "keyword_error",
+            TokenKind::KeywordExport => "keyword_export",
+            TokenKind::KeywordExtern => "keyword_extern",
+            TokenKind::KeywordFn => "keyword_fn",
+            TokenKind::KeywordFor => "keyword_for",
+            TokenKind::KeywordIf => "keyword_if",
+            TokenKind::KeywordInline => "keyword_inline",
+            TokenKind::KeywordLinksection => "keyword_linksection",
+            TokenKind::KeywordNoalias => "keyword_noalias",
+            TokenKind::KeywordNoinline => "keyword_noinline",
+            TokenKind::KeywordNosuspend => "keyword_nosuspend",
+            TokenKind::KeywordOpaque => "keyword_opaque",
+            TokenKind::KeywordOr => "keyword_or",
+            TokenKind::KeywordOrelse => "keyword_orelse",
+            TokenKind::KeywordPacked => "keyword_packed",
+            TokenKind::KeywordPub => "keyword_pub",
+            TokenKind::KeywordResume => "keyword_resume",
+            TokenKind::KeywordReturn => "keyword_return",
+            TokenKind::KeywordStruct => "keyword_struct",
+            TokenKind::KeywordSuspend => "keyword_suspend",
+            TokenKind::KeywordSwitch => "keyword_switch",
+            TokenKind::KeywordTest => "keyword_test",
+            TokenKind::KeywordThreadlocal => "keyword_threadlocal",
+            TokenKind::KeywordTry => "keyword_try",
+            TokenKind::KeywordUnion => "keyword_union",
+            TokenKind::KeywordUnreachable => "keyword_unreachable",
+            TokenKind::KeywordUsingnamespace => "keyword_usingnamespace",
+            TokenKind::KeywordVar => "keyword_var",
+            TokenKind::KeywordVolatile => "keyword_volatile",
+            TokenKind::KeywordWhile => "keyword_while",
+            TokenKind::RawIdentifier => "identifier",
+            TokenKind::IntegerLiteral => "number_literal",
+            TokenKind::FloatLiteral => "number_literal",
+            TokenKind::StringLiteral => "string_literal",
+            TokenKind::CharacterLiteral => "char_literal",
+            TokenKind::MultilineStringLiteralLine => "multiline_string_literal_line",
+            TokenKind::Bang => "bang",
+            TokenKind::BangEqual => "bang_equal",
+            TokenKind::Percent => "percent",
+            TokenKind::PercentEqual => "percent_equal",
+            TokenKind::And => "ampersand",
+            TokenKind::AndEqual => "ampersand_equal",
+            TokenKind::LParen => "l_paren",
+            TokenKind::RParen => "r_paren",
+            TokenKind::Star => "asterisk",
+            TokenKind::Star2 => "asterisk_asterisk",
+            TokenKind::StarEqual => "asterisk_equal",
+            TokenKind::StarPercent => "asterisk_percent",
+            TokenKind::StarPercentEqual => "asterisk_percent_equal",
+            TokenKind::StarOr => "asterisk_pipe",
+            TokenKind::StarOrEqual => "asterisk_pipe_equal",
+            TokenKind::Plus => "plus",
+            TokenKind::Plus2 => "plus_plus",
+            TokenKind::PlusEqual => "plus_equal",
+            TokenKind::PlusPercent => "plus_percent",
+            TokenKind::PlusPercentEqual => "plus_percent_equal",
+            TokenKind::PlusOr => "plus_pipe",
+            TokenKind::PlusOrEqual => "plus_pipe_equal",
+            TokenKind::Comma => "comma",
+            TokenKind::Minus => "minus",
+            TokenKind::MinusEqual => "minus_equal",
+            TokenKind::MinusPercent => "minus_percent",
+            TokenKind::MinusPercentEqual => "minus_percent_equal",
+            TokenKind::MinusOr => "minus_pipe",
+            TokenKind::MinusOrEqual => "minus_pipe_equal",
+            TokenKind::Dot => "period",
+            TokenKind::Dot2 => "ellipsis2",
+            TokenKind::Dot3 => "ellipsis3",
+            TokenKind::DotStar => "period_asterisk",
+            TokenKind::Slash => "slash",
+            TokenKind::SlashEqual => "slash_equal",
+            TokenKind::Colon => "colon",
+            TokenKind::Semicolon => "semicolon",
+            TokenKind::LAngle => "angle_bracket_left",
+            TokenKind::LAngleEqual => "angle_bracket_left_equal",
+            TokenKind::LAngle2 => "angle_bracket_angle_bracket_left",
+            TokenKind::LAngle2Equal => "angle_bracket_angle_bracket_left_equal",
+            TokenKind::LAngle2Or => "angle_bracket_angle_bracket_left_pipe",
+            TokenKind::LAngle2OrEqual => "angle_bracket_angle_bracket_left_pipe_equal",
+            TokenKind::Equal => "equal",
+            TokenKind::Equal2 => "equal_equal",
+            TokenKind::EqualRAngle => "equal_angle_bracket_right",
+            TokenKind::RAngle => "angle_bracket_right",
+            TokenKind::RAngleEqual => "angle_bracket_right_equal",
+            TokenKind::RAngle2 => "angle_bracket_angle_bracket_right",
+            TokenKind::RAngle2Equal => "angle_bracket_angle_bracket_right_equal",
+            TokenKind::Question => "question_mark",
+            TokenKind::LBracket => "l_bracket",
+            TokenKind::RBracket => "r_bracket",
+            TokenKind::Caret => "caret",
+            TokenKind::CaretEqual => "caret_equal",
+            TokenKind::LBrace => "l_brace",
+            TokenKind::Or => "pipe",
+            TokenKind::Or2 => "pipe_pipe",
+            TokenKind::OrEqual => "pipe_equal",
+            TokenKind::RBrace => "r_brace",
+            TokenKind::Tilde => "tilde",
+            TokenKind::Eof => "eof",
+            _ => return None,
+        })
+    }
+
+    /// The kinds that Zig's `std.zig.Token.Tag` named `tag` corresponds to.
+    /// Empty if there are none.
+    pub fn from_zig_tag(tag: &str) -> TokenSet {
+        TokenKind::ALL
+            .iter()
+            .filter(|kind| kind.zig_tag() == Some(tag))
+            .fold(TokenSet::EMPTY, |set, &kind| set.with(kind))
+    }
+}
+
+impl From<Token> for TokenKind {
+    fn from(token: Token) -> TokenKind {
+        match token {
+            Token::Invalid { .. } => TokenKind::Invalid,
+            Token::InvalidUtf8 => TokenKind::InvalidUtf8,
+            Token::Eof => TokenKind::Eof,
+            Token::Whitespace => TokenKind::Whitespace,
+            Token::Newline => TokenKind::Newline,
+            Token::Comment => TokenKind::Comment,
+            Token::DocComment => TokenKind::DocComment,
+            Token::ContainerDocComment => TokenKind::ContainerDocComment,
+            Token::Builtin => TokenKind::Builtin,
+            Token::Identifier => TokenKind::Identifier,
+            Token::Keyword(keyword) => TokenKind::from(keyword),
+            Token::RawIdentifier { .. } => TokenKind::RawIdentifier,
+            Token::IntegerLiteral { .. } => TokenKind::IntegerLiteral,
+            Token::FloatLiteral { .. } => TokenKind::FloatLiteral,
+            Token::StringLiteral { .. } => TokenKind::StringLiteral,
+            Token::CharacterLiteral { .. } => TokenKind::CharacterLiteral,
+            Token::MultilineStringLiteralLine => TokenKind::MultilineStringLiteralLine,
+            Token::Bang => TokenKind::Bang,
+            Token::BangEqual => TokenKind::BangEqual,
+            Token::Percent => TokenKind::Percent,
+            Token::PercentEqual => TokenKind::PercentEqual,
+            Token::And => TokenKind::And,
+            Token::And2 => TokenKind::And2,
+            Token::AndEqual => TokenKind::AndEqual,
+            Token::LParen => TokenKind::LParen,
+            Token::RParen => TokenKind::RParen,
+            Token::Star => TokenKind::Star,
+            Token::Star2 => TokenKind::Star2,
+            Token::StarEqual => TokenKind::StarEqual,
+            Token::StarPercent => TokenKind::StarPercent,
+            Token::StarPercentEqual => TokenKind::StarPercentEqual,
+            Token::StarOr => TokenKind::StarOr,
+            Token::StarOrEqual => TokenKind::StarOrEqual,
+            Token::Plus => TokenKind::Plus,
+            Token::Plus2 => TokenKind::Plus2,
+            Token::PlusEqual => TokenKind::PlusEqual,
+            Token::PlusPercent => TokenKind::PlusPercent,
+            Token::PlusPercentEqual => TokenKind::PlusPercentEqual,
+            Token::PlusOr => TokenKind::PlusOr,
+            Token::PlusOrEqual => TokenKind::PlusOrEqual,
+            Token::Comma => TokenKind::Comma,
+            Token::Minus => TokenKind::Minus,
+            Token::MinusEqual => TokenKind::MinusEqual,
+            Token::MinusPercent => TokenKind::MinusPercent,
+            Token::MinusPercentEqual => TokenKind::MinusPercentEqual,
+            Token::MinusOr => TokenKind::MinusOr,
+            Token::MinusOrEqual => TokenKind::MinusOrEqual,
+            Token::Dot => TokenKind::Dot,
+            Token::Dot2 => TokenKind::Dot2,
+            Token::Dot3 => TokenKind::Dot3,
+            Token::DotStar => TokenKind::DotStar,
+            Token::Slash => TokenKind::Slash,
+            Token::SlashEqual => TokenKind::SlashEqual,
+            Token::Colon => TokenKind::Colon,
+            Token::Semicolon => TokenKind::Semicolon,
+            Token::LAngle => TokenKind::LAngle,
+            Token::LAngleEqual => TokenKind::LAngleEqual,
+            Token::LAngle2 => TokenKind::LAngle2,
+            Token::LAngle2Equal => TokenKind::LAngle2Equal,
+            Token::LAngle2Or => TokenKind::LAngle2Or,
+            Token::LAngle2OrEqual => TokenKind::LAngle2OrEqual,
+            Token::Equal => TokenKind::Equal,
+            Token::Equal2 => TokenKind::Equal2,
+            Token::EqualRAngle => TokenKind::EqualRAngle,
+            Token::RAngle => TokenKind::RAngle,
+            Token::RAngleEqual => TokenKind::RAngleEqual,
+            Token::RAngle2 => TokenKind::RAngle2,
+            Token::RAngle2Equal => TokenKind::RAngle2Equal,
+            Token::Question => TokenKind::Question,
+            Token::At => TokenKind::At,
+            Token::LBracket => TokenKind::LBracket,
+            Token::RBracket => TokenKind::RBracket,
+            Token::Caret => TokenKind::Caret,
+            Token::CaretEqual => TokenKind::CaretEqual,
+            Token::LBrace => TokenKind::LBrace,
+            Token::Or => TokenKind::Or,
+            Token::Or2 => TokenKind::Or2,
+            Token::OrEqual => TokenKind::OrEqual,
+            Token::RBrace => TokenKind::RBrace,
+            Token::Tilde => TokenKind::Tilde,
+        }
+    }
+}
+
+impl From<Keyword> for TokenKind {
+    fn from(keyword: Keyword) -> TokenKind {
+        match keyword {
+            Keyword::Addrspace => TokenKind::KeywordAddrspace,
+            Keyword::Align => TokenKind::KeywordAlign,
+            Keyword::Allowzero => TokenKind::KeywordAllowzero,
+            Keyword::And => TokenKind::KeywordAnd,
+            Keyword::Anyframe => TokenKind::KeywordAnyframe,
+            Keyword::Anytype => TokenKind::KeywordAnytype,
+            Keyword::Asm => TokenKind::KeywordAsm,
+            Keyword::Async => TokenKind::KeywordAsync,
+            Keyword::Await => TokenKind::KeywordAwait,
+            Keyword::Break => TokenKind::KeywordBreak,
+            Keyword::Callconv => TokenKind::KeywordCallconv,
+            Keyword::Catch => TokenKind::KeywordCatch,
+            Keyword::Comptime => TokenKind::KeywordComptime,
+            Keyword::Const => TokenKind::KeywordConst,
+            Keyword::Continue => TokenKind::KeywordContinue,
+            Keyword::Defer => TokenKind::KeywordDefer,
+            Keyword::Else => TokenKind::KeywordElse,
+            Keyword::Enum => TokenKind::KeywordEnum,
+            Keyword::Errdefer => TokenKind::KeywordErrdefer,
+            Keyword::Error => TokenKind::KeywordError,
+            Keyword::Export => TokenKind::KeywordExport,
+            Keyword::Extern => TokenKind::KeywordExtern,
+            Keyword::Fn => TokenKind::KeywordFn,
+            Keyword::For => TokenKind::KeywordFor,
+            Keyword::If => TokenKind::KeywordIf,
+            Keyword::Inline => TokenKind::KeywordInline,
+            Keyword::Linksection => TokenKind::KeywordLinksection,
+            Keyword::Noalias => TokenKind::KeywordNoalias,
+            Keyword::Noinline => TokenKind::KeywordNoinline,
+            Keyword::Nosuspend => TokenKind::KeywordNosuspend,
+            Keyword::Opaque => TokenKind::KeywordOpaque,
+            Keyword::Or => TokenKind::KeywordOr,
+            Keyword::Orelse => TokenKind::KeywordOrelse,
+            Keyword::Packed => TokenKind::KeywordPacked,
+            Keyword::Pub => TokenKind::KeywordPub,
+            Keyword::Resume => TokenKind::KeywordResume,
+            Keyword::Return => TokenKind::KeywordReturn,
+            Keyword::Struct => TokenKind::KeywordStruct,
+            Keyword::Suspend => TokenKind::KeywordSuspend,
+            Keyword::Switch => TokenKind::KeywordSwitch,
+            Keyword::Test => TokenKind::KeywordTest,
+            Keyword::Threadlocal => TokenKind::KeywordThreadlocal,
+            Keyword::Try => TokenKind::KeywordTry,
+            Keyword::Union => TokenKind::KeywordUnion,
+            Keyword::Unreachable => TokenKind::KeywordUnreachable,
+            Keyword::Usingnamespace => TokenKind::KeywordUsingnamespace,
+            Keyword::Var => TokenKind::KeywordVar,
+            Keyword::Volatile => TokenKind::KeywordVolatile,
+            Keyword::While => TokenKind::KeywordWhile,
+        }
+    }
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(text) = self.fixed_text() {
+            return write!(f, "`{}`", text);
+        }
+        f.write_str(match self {
+            TokenKind::Invalid => "invalid token",
+            TokenKind::InvalidUtf8 => "invalid UTF-8",
+            TokenKind::Eof => "end of file",
+            TokenKind::Whitespace => "whitespace",
+            TokenKind::Newline => "newline",
+            TokenKind::Comment => "comment",
+            TokenKind::DocComment => "doc comment",
+            TokenKind::ContainerDocComment => "container doc comment",
+            TokenKind::Builtin => "builtin",
+            TokenKind::Identifier => "identifier",
+            TokenKind::RawIdentifier => "identifier",
+            TokenKind::IntegerLiteral => "integer literal",
+            TokenKind::FloatLiteral => "float literal",
+            TokenKind::StringLiteral => "string literal",
+            TokenKind::CharacterLiteral => "character literal",
+            TokenKind::MultilineStringLiteralLine => "multiline string literal",
+            _ => unreachable!(),
+        })
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        TokenKind::from(*self).fmt(f)
+    }
+}
+
+/// Returned when parsing a [`TokenKind`] from text that is not the fixed text
+/// of any kind.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ParseTokenKindError;
+
+impl FromStr for TokenKind {
+    type Err = ParseTokenKindError;
+
+    /// Parses the fixed text of a punctuation or keyword kind.
+    fn from_str(s: &str) -> Result<TokenKind, ParseTokenKindError> {
+        TokenKind::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.fixed_text() == Some(s))
+            .ok_or(ParseTokenKindError)
+    }
+}
+
+/// A set of [`TokenKind`]s, such as the tokens to skip to when recovering from
+/// a parse error.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct TokenSet([u64; 4]);
+
+impl TokenSet {
+    pub const EMPTY: TokenSet = TokenSet([0; 4]);
+
+    pub const fn new(kinds: &[TokenKind]) -> TokenSet {
+        let mut set = TokenSet::EMPTY;
+        let mut i = 0;
+        while i < kinds.len() {
+            set = set.with(kinds[i]);
+            i += 1;
+        }
+        set
+    }
+
+    /// This set with `kind` added.
+    pub const fn with(self, kind: TokenKind) -> TokenSet {
+        let mut words = self.0;
+        words[kind as usize / 64] |= 1 << (kind as usize % 64);
+        TokenSet(words)
+    }
+
+    pub const fn union(self, other: TokenSet) -> TokenSet {
+        let (a, b) = (self.0, other.0);
+        TokenSet([a[0] | b[0], a[1] | b[1], a[2] | b[2], a[3] | b[3]])
+    }
+
+    pub const fn contains(self, kind: TokenKind) -> bool {
+        self.0[kind as usize / 64] & 1 << (kind as usize % 64) != 0
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0[0] | self.0[1] | self.0[2] | self.0[3] == 0
+    }
+
+    /// The kinds in this set, in order.
+    pub fn iter(self) -> impl Iterator<Item = TokenKind> {
+        TokenKind::ALL
+            .iter()
+            .copied()
+            .filter(move |&kind| self.contains(kind))
+    }
+}
+
+impl From<TokenKind> for TokenSet {
+    fn from(kind: TokenKind) -> TokenSet {
+        TokenSet::EMPTY.with(kind)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lex;
+
+    #[test]
+    fn test_token_kind() {
+        for (i, &kind) in TokenKind::ALL.iter().enumerate() {
+            assert_eq!(kind as u8 as usize, i);
+            assert_eq!(TokenKind::from_u8(i as u8), Some(kind));
+        }
+        assert_eq!(TokenKind::from_u8(TokenKind::ALL.len() as u8), None);
+        assert_eq!(TokenKind::from(lex("fn").0), TokenKind::KeywordFn);
+        assert_eq!(TokenKind::from(lex("\"a").0), TokenKind::StringLiteral);
+        assert_eq!(TokenKind::from(lex("*%=").0), TokenKind::StarPercentEqual);
+        assert!(TokenKind::Invalid < TokenKind::Tilde);
+    }
+
+    #[test]
+    fn test_fixed_text() {
+        for kind in TokenKind::ALL {
+            if let Some(text) = kind.fixed_text() {
+                assert_eq!(lex(text).1, text.len(), "{}", text);
+                assert_eq!(TokenKind::from(lex(text).0), kind, "{}", text);
+                assert_eq!(text.parse(), Ok(kind));
+            }
+        }
+        assert_eq!(TokenKind::StarPercentEqual.fixed_text(), Some("*%="));
+        assert_eq!(TokenKind::KeywordWhile.fixed_text(), Some("while"));
+        assert_eq!(TokenKind::Identifier.fixed_text(), None);
+        assert_eq!("foo".parse::<TokenKind>(), Err(ParseTokenKindError));
+    }
+
+    #[test]
+    fn test_display() {
+        use core::fmt::Write;
+
+        struct Buf([u8; 32], usize);
+        impl Write for Buf {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.0[self.1..self.1 + s.len()].copy_from_slice(s.as_bytes());
+                self.1 += s.len();
+                Ok(())
+            }
+        }
+        let check = |token: Token, expected: &str| {
+            let mut buf = Buf([0; 32], 0);
+            write!(buf, "{}", token).unwrap();
+            assert_eq!(&buf.0[..buf.1], expected.as_bytes());
+        };
+        check(lex("<<=").0, "`<<=`");
+        check(lex("fn").0, "`fn`");
+        check(lex("\"a\"").0, "string literal");
+        check(lex("0x1p1").0, "float literal");
+    }
+
+    #[test]
+    fn test_zig_tag() {
+        assert_eq!(
+            TokenKind::StarPercentEqual.zig_tag(),
+            Some("asterisk_percent_equal")
+        );
+        assert_eq!(TokenKind::KeywordFn.zig_tag(), Some("keyword_fn"));
+        assert_eq!(TokenKind::Comment.zig_tag(), None);
+        for kind in TokenKind::ALL {
+            if let Some(tag) = kind.zig_tag() {
+                assert!(TokenKind::from_zig_tag(tag).contains(kind));
+            }
+        }
+        let numbers = TokenKind::from_zig_tag("number_literal");
+        assert!(numbers.contains(TokenKind::IntegerLiteral));
+        assert!(numbers.contains(TokenKind::FloatLiteral));
+        assert!(TokenKind::from_zig_tag("ellipsis2").contains(TokenKind::Dot2));
+        assert!(TokenKind::from_zig_tag("eof").contains(TokenKind::Eof));
+        assert!(TokenKind::from_zig_tag("nonsense").is_empty());
+    }
+
+    #[test]
+    fn test_token_set() {
+        const RECOVERY: TokenSet =
+            TokenSet::new(&[TokenKind::Semicolon, TokenKind::RBrace, TokenKind::RParen]);
+        const ITEMS: TokenSet = RECOVERY.union(TokenSet::new(&[TokenKind::KeywordFn]));
+        assert!(RECOVERY.contains(TokenKind::RBrace));
+        assert!(!RECOVERY.contains(TokenKind::KeywordFn));
+        assert!(ITEMS.contains(TokenKind::KeywordFn));
+        assert!(ITEMS.contains(TokenKind::Semicolon));
+        assert!(TokenSet::EMPTY.is_empty());
+        assert!(!TokenSet::from(TokenKind::Tilde).is_empty());
+        let mut kinds = ITEMS.iter();
+        assert_eq!(kinds.next(), Some(TokenKind::KeywordFn));
+        assert_eq!(kinds.next(), Some(TokenKind::RParen));
+        assert_eq!(kinds.next(), Some(TokenKind::Semicolon));
+        assert_eq!(kinds.next(), Some(TokenKind::RBrace));
+        assert_eq!(kinds.next(), None);
+        let all = TokenSet::new(&TokenKind::ALL);
+        assert!(TokenKind::ALL.iter().all(|&kind| all.contains(kind)));
+    }
+}
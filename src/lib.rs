@@ -1,5 +1,52 @@
 #![no_std]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+mod bigint;
+#[cfg(test)]
+mod conformance;
+mod dfa;
+mod diagnostic;
+mod escape;
+#[cfg(feature = "alloc")]
+mod incremental;
+mod kind;
+#[cfg(feature = "alloc")]
+mod line_index;
+mod number;
+#[cfg(feature = "std")]
+mod parallel;
+mod skip;
+mod stream;
+
+#[cfg(feature = "alloc")]
+pub use bigint::BigUint;
+
+pub use diagnostic::{diagnostics, diagnostics_bytes, DiagnosticKind, Diagnostics, LexDiagnostic};
+pub use escape::{
+    decode_char_literal, decode_raw_identifier, decode_string_literal, unescape_char_literal,
+    unescape_raw_identifier, unescape_string_literal, EscapeError, EscapeErrorKind,
+    StringLiteralBytes, Unescape, Unescaped,
+};
+#[cfg(feature = "alloc")]
+pub use incremental::{relex, TextEdit};
+pub use kind::{ParseTokenKindError, TokenKind, TokenSet};
+#[cfg(feature = "alloc")]
+pub use line_index::{Encoding, LineCol, LineIndex};
+#[cfg(feature = "alloc")]
+pub use number::{parse_float_literal_exact, parse_integer_literal_big, FloatLiteralValue};
+pub use number::{
+    parse_float_literal_f32, parse_float_literal_f64, parse_integer_literal, FloatLiteralError,
+    IntegerLiteralError,
+};
+#[cfg(feature = "std")]
+pub use parallel::lex_parallel;
+pub use stream::{StreamLexer, TokenSink};
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum IntegerBase {
     Binary,
@@ -14,15 +61,297 @@ pub enum FloatBase {
     Hexadecimal,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Keyword {
+    Addrspace,
+    Align,
+    Allowzero,
+    And,
+    Anyframe,
+    Anytype,
+    Asm,
+    Async,
+    Await,
+    Break,
+    Callconv,
+    Catch,
+    Comptime,
+    Const,
+    Continue,
+    Defer,
+    Else,
+    Enum,
+    Errdefer,
+    Error,
+    Export,
+    Extern,
+    Fn,
+    For,
+    If,
+    Inline,
+    Linksection,
+    Noalias,
+    Noinline,
+    Nosuspend,
+    Opaque,
+    Or,
+    Orelse,
+    Packed,
+    Pub,
+    Resume,
+    Return,
+    Struct,
+    Suspend,
+    Switch,
+    Test,
+    Threadlocal,
+    Try,
+    Union,
+    Unreachable,
+    Usingnamespace,
+    Var,
+    Volatile,
+    While,
+}
+
+impl Keyword {
+    /// Every keyword, in alphabetical order.
+    pub const ALL: [Keyword; 49] = [
+        Keyword::Addrspace,
+        Keyword::Align,
+        Keyword::Allowzero,
+        Keyword::And,
+        Keyword::Anyframe,
+        Keyword::Anytype,
+        Keyword::Asm,
+        Keyword::Async,
+        Keyword::Await,
+        Keyword::Break,
+        Keyword::Callconv,
+        Keyword::Catch,
+        Keyword::Comptime,
+        Keyword::Const,
+        Keyword::Continue,
+        Keyword::Defer,
+        Keyword::Else,
+        Keyword::Enum,
+        Keyword::Errdefer,
+        Keyword::Error,
+        Keyword::Export,
+        Keyword::Extern,
+        Keyword::Fn,
+        Keyword::For,
+        Keyword::If,
+        Keyword::Inline,
+        Keyword::Linksection,
+        Keyword::Noalias,
+        Keyword::Noinline,
+        Keyword::Nosuspend,
+        Keyword::Opaque,
+        Keyword::Or,
+        Keyword::Orelse,
+        Keyword::Packed,
+        Keyword::Pub,
+        Keyword::Resume,
+        Keyword::Return,
+        Keyword::Struct,
+        Keyword::Suspend,
+        Keyword::Switch,
+        Keyword::Test,
+        Keyword::Threadlocal,
+        Keyword::Try,
+        Keyword::Union,
+        Keyword::Unreachable,
+        Keyword::Usingnamespace,
+        Keyword::Var,
+        Keyword::Volatile,
+        Keyword::While,
+    ];
+
+    pub fn from_identifier(s: &str) -> Option<Keyword> {
+        Some(match s {
+            "addrspace" => Keyword::Addrspace,
+            "align" => Keyword::Align,
+            "allowzero" => Keyword::Allowzero,
+            "and" => Keyword::And,
+            "anyframe" => Keyword::Anyframe,
+            "anytype" => Keyword::Anytype,
+            "asm" => Keyword::Asm,
+            "async" => Keyword::Async,
+            "await" => Keyword::Await,
+            "break" => Keyword::Break,
+            "callconv" => Keyword::Callconv,
+            "catch" => Keyword::Catch,
+            "comptime" => Keyword::Comptime,
+            "const" => Keyword::Const,
+            "continue" => Keyword::Continue,
+            "defer" => Keyword::Defer,
+            "else" => Keyword::Else,
+            "enum" => Keyword::Enum,
+            "errdefer" => Keyword::Errdefer,
+            "error" => Keyword::Error,
+            "export" => Keyword::Export,
+            "extern" => Keyword::Extern,
+            "fn" => Keyword::Fn,
+            "for" => Keyword::For,
+            "if" => Keyword::If,
+            "inline" => Keyword::Inline,
+            "linksection" => Keyword::Linksection,
+            "noalias" => Keyword::Noalias,
+            "noinline" => Keyword::Noinline,
+            "nosuspend" => Keyword::Nosuspend,
+            "opaque" => Keyword::Opaque,
+            "or" => Keyword::Or,
+            "orelse" => Keyword::Orelse,
+            "packed" => Keyword::Packed,
+            "pub" => Keyword::Pub,
+            "resume" => Keyword::Resume,
+            "return" => Keyword::Return,
+            "struct" => Keyword::Struct,
+            "suspend" => Keyword::Suspend,
+            "switch" => Keyword::Switch,
+            "test" => Keyword::Test,
+            "threadlocal" => Keyword::Threadlocal,
+            "try" => Keyword::Try,
+            "union" => Keyword::Union,
+            "unreachable" => Keyword::Unreachable,
+            "usingnamespace" => Keyword::Usingnamespace,
+            "var" => Keyword::Var,
+            "volatile" => Keyword::Volatile,
+            "while" => Keyword::While,
+            _ => return None,
+        })
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Keyword::Addrspace => "addrspace",
+            Keyword::Align => "align",
+            Keyword::Allowzero => "allowzero",
+            Keyword::And => "and",
+            Keyword::Anyframe => "anyframe",
+            Keyword::Anytype => "anytype",
+            Keyword::Asm => "asm",
+            Keyword::Async => "async",
+            Keyword::Await => "await",
+            Keyword::Break => "break",
+            Keyword::Callconv => "callconv",
+            Keyword::Catch => "catch",
+            Keyword::Comptime => "comptime",
+            Keyword::Const => "const",
+            Keyword::Continue => "continue",
+            Keyword::Defer => "defer",
+            Keyword::Else => "else",
+            Keyword::Enum => "enum",
+            Keyword::Errdefer => "errdefer",
+            Keyword::Error => "error",
+            Keyword::Export => "export",
+            Keyword::Extern => "extern",
+            Keyword::Fn => "fn",
+            Keyword::For => "for",
+            Keyword::If => "if",
+            Keyword::Inline => "inline",
+            Keyword::Linksection => "linksection",
+            Keyword::Noalias => "noalias",
+            Keyword::Noinline => "noinline",
+            Keyword::Nosuspend => "nosuspend",
+            Keyword::Opaque => "opaque",
+            Keyword::Or => "or",
+            Keyword::Orelse => "orelse",
+            Keyword::Packed => "packed",
+            Keyword::Pub => "pub",
+            Keyword::Resume => "resume",
+            Keyword::Return => "return",
+            Keyword::Struct => "struct",
+            Keyword::Suspend => "suspend",
+            Keyword::Switch => "switch",
+            Keyword::Test => "test",
+            Keyword::Threadlocal => "threadlocal",
+            Keyword::Try => "try",
+            Keyword::Union => "union",
+            Keyword::Unreachable => "unreachable",
+            Keyword::Usingnamespace => "usingnamespace",
+            Keyword::Var => "var",
+            Keyword::Volatile => "volatile",
+            Keyword::While => "while",
+        }
+    }
+
+    const fn from_identifier_const(s: &[u8]) -> Option<Keyword> {
+        let mut i = 0;
+        while i < Keyword::ALL.len() {
+            let keyword = Keyword::ALL[i].as_str().as_bytes();
+            if keyword.len() == s.len() {
+                let mut j = 0;
+                while j < s.len() && keyword[j] == s[j] {
+                    j += 1;
+                }
+                if j == s.len() {
+                    return Some(Keyword::ALL[i]);
+                }
+            }
+            i += 1;
+        }
+        None
+    }
+}
+
+/// Why a run of characters could not be lexed as a token.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum InvalidCharacterKind {
+    /// Non-ASCII characters outside of a string or comment, such as an
+    /// identifier that is not written in ASCII.
+    NonAscii,
+    /// A control character other than a tab or line break.
+    ControlCharacter,
+    /// A `\` that does not start a `\\` string line.
+    Backslash,
+    /// A `\r` that is not followed by `\n`.
+    LoneCarriageReturn,
+    /// An ASCII character that does not start any token, such as `$` or `#`.
+    UnknownSymbol,
+    /// U+2028 or U+2029, which strict lexing also rejects in comments and
+    /// literals.
+    LineSeparator,
+}
+
+impl InvalidCharacterKind {
+    pub const fn of(c: char) -> InvalidCharacterKind {
+        match c {
+            '\r' => InvalidCharacterKind::LoneCarriageReturn,
+            '\\' => InvalidCharacterKind::Backslash,
+            '\u{0}'..='\u{1f}' | '\u{7f}'..='\u{9f}' => InvalidCharacterKind::ControlCharacter,
+            '\u{2028}' | '\u{2029}' => InvalidCharacterKind::LineSeparator,
+            '\u{80}'.. => InvalidCharacterKind::NonAscii,
+            _ => InvalidCharacterKind::UnknownSymbol,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Token {
-    Invalid,
+    /// A run of characters of the same [`InvalidCharacterKind`], starting with
+    /// `character`. With [`LexOptions::strict`], also the rest of a line with
+    /// a rejected `character` in a comment or literal, or a `.**`.
+    Invalid {
+        kind: InvalidCharacterKind,
+        character: char,
+    },
+    InvalidUtf8,
+    /// Returned for empty input.
+    Eof,
     Whitespace,
+    /// `\n` or `\r\n`, with [`LexOptions::newlines`].
+    Newline,
     Comment,
     DocComment,
+    ContainerDocComment,
     Builtin,
     Identifier,
+    Keyword(Keyword),
     RawIdentifier {
+        /// `@""`, which is only reported with [`LexOptions::strict`].
+        is_empty: bool,
         is_unterminated: bool,
         has_invalid_escape: bool,
     },
@@ -62,16 +391,22 @@ pub enum Token {
     StarEqual,         // *=
     StarPercent,       // *%
     StarPercentEqual,  // *%=
+    StarOr,            // *|
+    StarOrEqual,       // *|=
     Plus,              // +
     Plus2,             // ++
     PlusEqual,         // +=
     PlusPercent,       // +%
     PlusPercentEqual,  // +%=
+    PlusOr,            // +|
+    PlusOrEqual,       // +|=
     Comma,             // ,
     Minus,             // -
     MinusEqual,        // -=
     MinusPercent,      // -%
     MinusPercentEqual, // -%=
+    MinusOr,           // -|
+    MinusOrEqual,      // -|=
     Dot,               // .
     Dot2,              // ..
     Dot3,              // ...
@@ -84,6 +419,8 @@ pub enum Token {
     LAngleEqual,       // <=
     LAngle2,           // <<
     LAngle2Equal,      // <<=
+    LAngle2Or,         // <<|
+    LAngle2OrEqual,    // <<|=
     Equal,             // =
     Equal2,            // ==
     EqualRAngle,       // =>
@@ -102,34 +439,83 @@ pub enum Token {
     Or2,               // ||
     OrEqual,           // |=
     RBrace,            // }
+    Tilde,             // ~
+}
+
+impl Token {
+    /// Whether the lexer found something wrong with this token. Use
+    /// [`diagnostics`] to find out what and where.
+    pub fn is_error(self) -> bool {
+        match self {
+            Token::Invalid { .. } | Token::InvalidUtf8 => true,
+            Token::StringLiteral {
+                is_unterminated,
+                has_invalid_escape,
+            } => is_unterminated || has_invalid_escape,
+            Token::RawIdentifier {
+                is_empty,
+                is_unterminated,
+                has_invalid_escape,
+            }
+            | Token::CharacterLiteral {
+                is_empty,
+                is_unterminated,
+                has_invalid_escape,
+            } => is_empty || is_unterminated || has_invalid_escape,
+            Token::IntegerLiteral {
+                is_unterminated,
+                has_invalid_characters,
+                has_duplicate_underscore,
+                ..
+            }
+            | Token::FloatLiteral {
+                is_unterminated,
+                has_invalid_characters,
+                has_duplicate_underscore,
+                ..
+            } => is_unterminated || has_invalid_characters || has_duplicate_underscore,
+            _ => false,
+        }
+    }
 }
 
-#[derive(PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 enum EscapeKind {
     RawIdentifier,
     StringLiteral,
     CharacterLiteral,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 enum State {
     Start,
-    Invalid,
+    Invalid {
+        kind: InvalidCharacterKind,
+    },
     Whitespace,
+    CarriageReturn,
+    WhitespaceCarriageReturn,
     Bang,
     Percent,
     And,
     Star,
     StarPercent,
+    StarOr,
     Plus,
     PlusPercent,
+    PlusOr,
     Minus,
     MinusPercent,
+    MinusOr,
     Dot,
+    DotStar,
     Dot2,
     Slash,
     Slash2,
+    Slash3,
     LAngle,
     LAngle2,
+    LAngle2Or,
     Equal,
     RAngle,
     RAngle2,
@@ -138,6 +524,12 @@ enum State {
     Or,
     Comment,
     DocComment,
+    ContainerDocComment,
+    /// The rest of a line after a character that strict lexing rejects.
+    InvalidLine,
+    /// A `\r` in a comment or literal with strict lexing.
+    LineCarriageReturn,
+    Backslash,
     MultilineStringLiteralLine,
     Identifier,
     Builtin,
@@ -253,6 +645,31 @@ enum State {
     },
 }
 
+/// What `step` returns for a run of invalid characters of `kind`. The
+/// character is filled in by `resolve` from the start of the run; it is never
+/// a `\n`.
+const fn invalid(kind: InvalidCharacterKind) -> Token {
+    Token::Invalid {
+        kind,
+        character: '\n',
+    }
+}
+
+/// What `step` returns for a line with a character that strict lexing
+/// rejects. The kind and character are filled in by `invalid_line`.
+const INVALID_LINE: Token = Token::Invalid {
+    kind: InvalidCharacterKind::ControlCharacter,
+    character: '\0',
+};
+
+/// Whether strict lexing rejects `c` in a comment or literal.
+const fn is_rejected_in_line(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0}'..='\u{8}' | '\u{b}'..='\u{1f}' | '\u{7f}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
 enum Step {
     Continue(State),
     Reprocess(State),
@@ -261,10 +678,15 @@ enum Step {
     End(Token),
 }
 
-fn step(state: State, c: Option<char>) -> Step {
+/// Advances `state` by `c`, or by the end of input if `c` is `None`, as
+/// changed by `options`. The options only change how characters are handled,
+/// never the end of input.
+const fn step(state: State, c: Option<char>, options: LexOptions) -> Step {
     match state {
         State::Start => match c {
-            Some(' ') | Some('\t') | Some('\r') | Some('\n') => Step::Continue(State::Whitespace),
+            Some('\n') if options.newlines => Step::End(Token::Newline),
+            Some(' ') | Some('\t') | Some('\n') => Step::Continue(State::Whitespace),
+            Some('\r') => Step::Continue(State::CarriageReturn),
             Some('!') => Step::Continue(State::Bang),
             Some('"') => Step::Continue(State::StringLiteral {
                 is_empty: true,
@@ -300,26 +722,42 @@ fn step(state: State, c: Option<char>) -> Step {
             Some('@') => Step::Continue(State::At),
             Some('A'..='Z') | Some('a'..='z') | Some('_') => Step::Continue(State::Identifier),
             Some('[') => Step::End(Token::LBracket),
-            Some('\\') => Step::Continue(State::MultilineStringLiteralLine),
+            Some('\\') => Step::Continue(State::Backslash),
             Some(']') => Step::End(Token::RBracket),
             Some('^') => Step::Continue(State::Caret),
             Some('{') => Step::End(Token::LBrace),
             Some('|') => Step::Continue(State::Or),
             Some('}') => Step::End(Token::RBrace),
-            Some(_) => Step::Continue(State::Invalid),
-            None => Step::Abort(Token::Invalid),
+            Some('~') => Step::End(Token::Tilde),
+            Some(c) => Step::Continue(State::Invalid {
+                kind: InvalidCharacterKind::of(c),
+            }),
+            None => Step::Abort(Token::Eof),
         },
-        State::Invalid => match step(State::Start, c) {
-            Step::Continue(State::Invalid) | Step::Abort(Token::Invalid) => match c {
-                Some(_) => Step::Continue(State::Invalid),
-                None => Step::Abort(Token::Invalid),
-            },
-            _ => Step::Abort(Token::Invalid),
+        State::Invalid { kind } => match step(State::Start, c, options) {
+            Step::Continue(State::Invalid { kind: next }) if next as u8 == kind as u8 => {
+                Step::Continue(State::Invalid { kind })
+            }
+            _ => Step::Abort(invalid(kind)),
         },
         State::Whitespace => match c {
-            Some(' ') | Some('\t') | Some('\r') | Some('\n') => Step::Continue(State::Whitespace),
+            Some('\n') | Some('\r') if options.newlines => Step::Abort(Token::Whitespace),
+            Some(' ') | Some('\t') | Some('\n') => Step::Continue(State::Whitespace),
+            Some('\r') => Step::Continue(State::WhitespaceCarriageReturn),
             Some(_) | None => Step::Abort(Token::Whitespace),
         },
+        State::CarriageReturn => match c {
+            Some('\n') if options.newlines => Step::End(Token::Newline),
+            Some('\n') => Step::Continue(State::Whitespace),
+            Some(_) | None => Step::Abort(Token::Invalid {
+                kind: InvalidCharacterKind::LoneCarriageReturn,
+                character: '\r',
+            }),
+        },
+        State::WhitespaceCarriageReturn => match c {
+            Some('\n') => Step::Continue(State::Whitespace),
+            Some(_) | None => Step::Backtrack,
+        },
         State::Bang => match c {
             Some('=') => Step::End(Token::BangEqual),
             Some(_) | None => Step::Abort(Token::Bang),
@@ -337,36 +775,59 @@ fn step(state: State, c: Option<char>) -> Step {
             Some('*') => Step::End(Token::Star2),
             Some('=') => Step::End(Token::StarEqual),
             Some('%') => Step::Continue(State::StarPercent),
+            Some('|') => Step::Continue(State::StarOr),
             Some(_) | None => Step::Abort(Token::Star),
         },
         State::StarPercent => match c {
             Some('=') => Step::End(Token::StarPercentEqual),
             Some(_) | None => Step::Abort(Token::StarPercent),
         },
+        State::StarOr => match c {
+            Some('=') => Step::End(Token::StarOrEqual),
+            Some(_) | None => Step::Abort(Token::StarOr),
+        },
         State::Plus => match c {
             Some('+') => Step::End(Token::Plus2),
             Some('=') => Step::End(Token::PlusEqual),
             Some('%') => Step::Continue(State::PlusPercent),
+            Some('|') => Step::Continue(State::PlusOr),
             Some(_) | None => Step::Abort(Token::Plus),
         },
         State::PlusPercent => match c {
             Some('=') => Step::End(Token::PlusPercentEqual),
             Some(_) | None => Step::Abort(Token::PlusPercent),
         },
+        State::PlusOr => match c {
+            Some('=') => Step::End(Token::PlusOrEqual),
+            Some(_) | None => Step::Abort(Token::PlusOr),
+        },
         State::Minus => match c {
             Some('=') => Step::End(Token::MinusEqual),
             Some('%') => Step::Continue(State::MinusPercent),
+            Some('|') => Step::Continue(State::MinusOr),
             Some(_) | None => Step::Abort(Token::Minus),
         },
         State::MinusPercent => match c {
             Some('=') => Step::End(Token::MinusPercentEqual),
             Some(_) | None => Step::Abort(Token::MinusPercent),
         },
+        State::MinusOr => match c {
+            Some('=') => Step::End(Token::MinusOrEqual),
+            Some(_) | None => Step::Abort(Token::MinusOr),
+        },
         State::Dot => match c {
             Some('.') => Step::Continue(State::Dot2),
+            Some('*') if options.strict => Step::Continue(State::DotStar),
             Some('*') => Step::End(Token::DotStar),
             Some(_) | None => Step::Abort(Token::Dot),
         },
+        State::DotStar => match c {
+            Some('*') => Step::End(Token::Invalid {
+                kind: InvalidCharacterKind::UnknownSymbol,
+                character: '*',
+            }),
+            Some(_) | None => Step::Abort(Token::DotStar),
+        },
         State::Dot2 => match c {
             Some('.') => Step::End(Token::Dot3),
             Some(_) | None => Step::Abort(Token::Dot2),
@@ -377,9 +838,13 @@ fn step(state: State, c: Option<char>) -> Step {
             Some(_) | None => Step::Abort(Token::Slash),
         },
         State::Slash2 => match c {
-            Some('\n') | None => Step::Abort(Token::Comment),
-            Some('/') => Step::Continue(State::DocComment),
-            Some(_) => Step::Continue(State::Comment),
+            Some('/') => Step::Continue(State::Slash3),
+            Some('!') => Step::Continue(State::ContainerDocComment),
+            Some(_) | None => Step::Reprocess(State::Comment),
+        },
+        State::Slash3 => match c {
+            Some('/') => Step::Continue(State::Comment),
+            Some(_) | None => Step::Reprocess(State::DocComment),
         },
         State::LAngle => match c {
             Some('=') => Step::End(Token::LAngleEqual),
@@ -388,8 +853,13 @@ fn step(state: State, c: Option<char>) -> Step {
         },
         State::LAngle2 => match c {
             Some('=') => Step::End(Token::LAngle2Equal),
+            Some('|') => Step::Continue(State::LAngle2Or),
             Some(_) | None => Step::Abort(Token::LAngle2),
         },
+        State::LAngle2Or => match c {
+            Some('=') => Step::End(Token::LAngle2OrEqual),
+            Some(_) | None => Step::Abort(Token::LAngle2Or),
+        },
         State::Equal => match c {
             Some('=') => Step::End(Token::Equal2),
             Some('>') => Step::End(Token::EqualRAngle),
@@ -424,14 +894,46 @@ fn step(state: State, c: Option<char>) -> Step {
         },
         State::Comment => match c {
             Some('\n') | None => Step::Abort(Token::Comment),
+            Some(c) if options.strict && is_rejected_in_line(c) => {
+                Step::Reprocess(State::InvalidLine)
+            }
             Some(_) => Step::Continue(State::Comment),
         },
         State::DocComment => match c {
             Some('\n') | None => Step::Abort(Token::DocComment),
+            Some(c) if options.strict && is_rejected_in_line(c) => {
+                Step::Reprocess(State::InvalidLine)
+            }
             Some(_) => Step::Continue(State::DocComment),
         },
+        State::ContainerDocComment => match c {
+            Some('\n') | None => Step::Abort(Token::ContainerDocComment),
+            Some(c) if options.strict && is_rejected_in_line(c) => {
+                Step::Reprocess(State::InvalidLine)
+            }
+            Some(_) => Step::Continue(State::ContainerDocComment),
+        },
+        State::InvalidLine => match c {
+            Some('\r') => Step::Continue(State::LineCarriageReturn),
+            Some('\n') | None => Step::Abort(INVALID_LINE),
+            Some(_) => Step::Continue(State::InvalidLine),
+        },
+        State::LineCarriageReturn => match c {
+            Some('\n') => Step::Backtrack,
+            Some(_) | None => Step::Reprocess(State::InvalidLine),
+        },
+        State::Backslash => match c {
+            Some('\\') => Step::Continue(State::MultilineStringLiteralLine),
+            Some(_) | None => Step::Abort(Token::Invalid {
+                kind: InvalidCharacterKind::Backslash,
+                character: '\\',
+            }),
+        },
         State::MultilineStringLiteralLine => match c {
             Some('\n') | None => Step::Abort(Token::MultilineStringLiteralLine),
+            Some(c) if options.strict && is_rejected_in_line(c) => {
+                Step::Reprocess(State::InvalidLine)
+            }
             Some(_) => Step::Continue(State::MultilineStringLiteralLine),
         },
         State::Identifier => match c {
@@ -456,27 +958,33 @@ fn step(state: State, c: Option<char>) -> Step {
                 escape_kind,
                 has_invalid_escape,
             }),
-            Some('"') if escape_kind != EscapeKind::CharacterLiteral => match escape_kind {
-                EscapeKind::RawIdentifier => Step::End(Token::RawIdentifier {
-                    is_unterminated: false,
-                    has_invalid_escape,
-                }),
-                EscapeKind::StringLiteral => Step::End(Token::StringLiteral {
-                    is_unterminated: false,
-                    has_invalid_escape,
-                }),
-                EscapeKind::CharacterLiteral => unreachable!(),
-            },
-            Some('\'') if escape_kind == EscapeKind::CharacterLiteral => match escape_kind {
-                EscapeKind::CharacterLiteral => Step::End(Token::CharacterLiteral {
-                    is_empty,
-                    is_unterminated: false,
-                    has_invalid_escape,
-                }),
-                EscapeKind::RawIdentifier | EscapeKind::StringLiteral => unreachable!(),
-            },
+            Some('"') if !matches!(escape_kind, EscapeKind::CharacterLiteral) => {
+                match escape_kind {
+                    EscapeKind::RawIdentifier => Step::End(Token::RawIdentifier {
+                        is_empty: options.strict && is_empty,
+                        is_unterminated: false,
+                        has_invalid_escape,
+                    }),
+                    EscapeKind::StringLiteral => Step::End(Token::StringLiteral {
+                        is_unterminated: false,
+                        has_invalid_escape,
+                    }),
+                    EscapeKind::CharacterLiteral => unreachable!(),
+                }
+            }
+            Some('\'') if matches!(escape_kind, EscapeKind::CharacterLiteral) => {
+                match escape_kind {
+                    EscapeKind::CharacterLiteral => Step::End(Token::CharacterLiteral {
+                        is_empty,
+                        is_unterminated: false,
+                        has_invalid_escape,
+                    }),
+                    EscapeKind::RawIdentifier | EscapeKind::StringLiteral => unreachable!(),
+                }
+            }
             Some('\n') | None => match escape_kind {
                 EscapeKind::RawIdentifier => Step::Abort(Token::RawIdentifier {
+                    is_empty: false,
                     is_unterminated: true,
                     has_invalid_escape,
                 }),
@@ -490,6 +998,9 @@ fn step(state: State, c: Option<char>) -> Step {
                     has_invalid_escape,
                 }),
             },
+            Some(c) if options.strict && is_rejected_in_line(c) => {
+                Step::Reprocess(State::InvalidLine)
+            }
             Some(_) => Step::Continue(State::StringLiteral {
                 is_empty: false,
                 escape_kind,
@@ -631,6 +1142,14 @@ fn step(state: State, c: Option<char>) -> Step {
                 has_invalid_characters: false,
                 has_duplicate_underscore: false,
             }),
+            Some('0'..='9') if options.strict => Step::Continue(State::Number {
+                has_invalid_characters: true,
+                has_duplicate_underscore: false,
+            }),
+            Some('_') if options.strict => Step::Continue(State::NumberUnderscore {
+                has_invalid_characters: true,
+                has_duplicate_underscore: false,
+            }),
             Some(_) | None => Step::Reprocess(State::Number {
                 has_invalid_characters: false,
                 has_duplicate_underscore: false,
@@ -931,9 +1450,9 @@ fn step(state: State, c: Option<char>) -> Step {
                 has_duplicate_underscore,
             }),
             Some('_') => Step::Continue(State::NumberHex {
-                is_unterminated: false,
+                is_unterminated: true,
                 has_invalid_characters,
-                has_duplicate_underscore: false,
+                has_duplicate_underscore: true,
             }),
             Some(_) | None => Step::Abort(Token::IntegerLiteral {
                 base: IntegerBase::Hexadecimal,
@@ -958,7 +1477,7 @@ fn step(state: State, c: Option<char>) -> Step {
                 has_invalid_characters,
                 has_duplicate_underscore,
             }),
-            Some('.') => Step::Backtrack,
+            Some('.') if is_unterminated => Step::Backtrack,
             Some('p') | Some('P') => Step::Continue(State::NumberHexExponent {
                 has_invalid_characters,
                 has_duplicate_underscore,
@@ -1068,7 +1587,7 @@ fn step(state: State, c: Option<char>) -> Step {
                 has_invalid_characters,
                 has_duplicate_underscore,
             }),
-            Some('.') => Step::Backtrack,
+            Some('.') if is_unterminated => Step::Backtrack,
             Some('e') | Some('E') => Step::Continue(State::NumberExponent {
                 has_invalid_characters,
                 has_duplicate_underscore,
@@ -1110,15 +1629,325 @@ fn step(state: State, c: Option<char>) -> Step {
 }
 
 pub fn lex(s: &str) -> (Token, usize) {
+    match dfa::lex_token(s) {
+        (Token::Identifier, len) => match Keyword::from_identifier(&s[..len]) {
+            Some(keyword) => (Token::Keyword(keyword), len),
+            None => (Token::Identifier, len),
+        },
+        (token, len) => resolve(s.as_bytes(), token, len),
+    }
+}
+
+/// Fills in invalid tokens and checks escape values in what `step` returns
+/// for the first `len` bytes of `s`.
+const fn resolve(s: &[u8], token: Token, len: usize) -> (Token, usize) {
+    match token {
+        INVALID_LINE => (invalid_line(s, len), len),
+        Token::Invalid {
+            kind,
+            character: '\n',
+        } => match decode_utf8(s, 0) {
+            Some((character, _)) => (Token::Invalid { kind, character }, len),
+            None => unreachable!(),
+        },
+        _ => (check_escapes(s, token, len), len),
+    }
+}
+
+const fn invalid_line(s: &[u8], len: usize) -> Token {
+    let mut pos = 0;
+    while pos < len {
+        let character = match s.split_at(pos).1 {
+            [b, ..] if b.is_ascii() => *b as char,
+            [0xc2, 0x85, ..] => '\u{85}',
+            [0xe2, 0x80, 0xa8, ..] => '\u{2028}',
+            [0xe2, 0x80, 0xa9, ..] => '\u{2029}',
+            _ => {
+                pos += 1;
+                continue;
+            }
+        };
+        if is_rejected_in_line(character) {
+            let kind = InvalidCharacterKind::of(character);
+            return Token::Invalid { kind, character };
+        }
+        pos += 1;
+    }
+    unreachable!()
+}
+
+/// Checks the values of the escapes in a string, character or raw identifier
+/// literal of `len` bytes, which `step` only checks the syntax of. Other
+/// tokens are returned unchanged. A zero byte in a raw identifier is left to
+/// [`diagnostics`], as Zig's tokenizer accepts it.
+const fn check_escapes(s: &[u8], token: Token, len: usize) -> Token {
+    match token {
+        Token::RawIdentifier {
+            has_invalid_escape: false,
+            ..
+        }
+        | Token::StringLiteral {
+            has_invalid_escape: false,
+            ..
+        }
+        | Token::CharacterLiteral {
+            has_invalid_escape: false,
+            ..
+        } => {}
+        _ => return token,
+    }
+    let mut i = 0;
+    while i + 1 < len {
+        if s[i] != b'\\' {
+            i += 1;
+            continue;
+        }
+        // Escapes are well-formed here: `\x` is followed by two hex digits and
+        // `\u` by `{`, at least one hex digit and `}`.
+        let (is_unicode, max_digits) = match s[i + 1] {
+            b'x' => (false, 2),
+            b'u' => (true, usize::MAX),
+            _ => {
+                i += 2;
+                continue;
+            }
+        };
+        i += 2 + is_unicode as usize;
+        let (mut digits, mut value) = (0, 0u32);
+        while i < len && digits < max_digits && s[i].is_ascii_hexdigit() {
+            value = value
+                .saturating_mul(16)
+                .saturating_add(escape::hex_value(s[i]));
+            digits += 1;
+            i += 1;
+        }
+        if escape::check_escape_value(is_unicode, digits as u32, value, false).is_some() {
+            return with_invalid_escape(token);
+        }
+    }
+    token
+}
+
+/// Sets `has_invalid_escape` on a string, character or raw identifier literal.
+const fn with_invalid_escape(token: Token) -> Token {
+    match token {
+        Token::RawIdentifier {
+            is_empty,
+            is_unterminated,
+            ..
+        } => Token::RawIdentifier {
+            is_empty,
+            is_unterminated,
+            has_invalid_escape: true,
+        },
+        Token::StringLiteral {
+            is_unterminated, ..
+        } => Token::StringLiteral {
+            is_unterminated,
+            has_invalid_escape: true,
+        },
+        Token::CharacterLiteral {
+            is_empty,
+            is_unterminated,
+            ..
+        } => Token::CharacterLiteral {
+            is_empty,
+            is_unterminated,
+            has_invalid_escape: true,
+        },
+        _ => token,
+    }
+}
+
+/// Changes to what [`lex`] returns. The default changes nothing.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct LexOptions {
+    /// Returns line breaks as [`Token::Newline`] instead of as part of
+    /// [`Token::Whitespace`], which then only covers spaces and tabs.
+    pub newlines: bool,
+    /// Follows Zig's rules where the tolerant default does not:
+    ///
+    /// - [`Lexer`] and [`BytesLexer`] skip a leading UTF-8 byte order mark.
+    /// - A control character other than tab, or U+0085, U+2028 or U+2029, in
+    ///   a comment or literal makes the rest of the line a [`Token::Invalid`].
+    ///   So does a `\r` that is not followed by `\n`; comments and literals
+    ///   end before a `\r\n`.
+    /// - `@""` is a [`Token::RawIdentifier`] with `is_empty` set.
+    /// - `.**` is a [`Token::Invalid`] for the second `*`.
+    /// - Digits after a leading `0`, as in `0123`, are invalid characters.
+    ///
+    /// Strict lexing checks every character, so it is slower.
+    pub strict: bool,
+}
+
+/// The options [`lex`] uses, for code that cannot call `Default::default`.
+const DEFAULT_OPTIONS: LexOptions = LexOptions {
+    newlines: false,
+    strict: false,
+};
+
+pub fn lex_with_options(s: &str, options: LexOptions) -> (Token, usize) {
+    // The table-driven lexer only knows the default options, which give the
+    // same result for anything but whitespace in newlines mode.
+    match s.as_bytes().first() {
+        _ if options.strict => lex_bytes_with(s.as_bytes(), options),
+        Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') if options.newlines => {
+            lex_bytes_with(s.as_bytes(), options)
+        }
+        _ => lex(s),
+    }
+}
+
+/// Like [`lex`], but usable in constant expressions, at the cost of speed.
+pub const fn lex_const(s: &str) -> (Token, usize) {
+    let bytes = s.as_bytes();
+    let mut state = State::Start;
+    let (mut cur_pos, mut last_pos, mut last_state) = (0, 0, State::Start);
+    let (token, len) = 'outer: loop {
+        let (c, len) = match decode_utf8(bytes, cur_pos) {
+            Some((c, len)) => (Some(c), len),
+            None => (None, 0),
+        };
+        let before = state;
+        loop {
+            match step(state, c, DEFAULT_OPTIONS) {
+                Step::Continue(new_state) => {
+                    last_pos = cur_pos;
+                    last_state = before;
+                    cur_pos += len;
+                    state = new_state;
+                    continue 'outer;
+                }
+                Step::Reprocess(new_state) => state = new_state,
+                Step::Backtrack => break 'outer (finish(last_state), last_pos),
+                Step::Abort(kind) => break 'outer (kind, cur_pos),
+                Step::End(kind) => break 'outer (kind, cur_pos + len),
+            }
+        }
+    };
+    match token {
+        Token::Identifier => match Keyword::from_identifier_const(bytes.split_at(len).0) {
+            Some(keyword) => (Token::Keyword(keyword), len),
+            None => (Token::Identifier, len),
+        },
+        _ => resolve(bytes, token, len),
+    }
+}
+
+/// Decodes the character at `pos` of valid UTF-8 `bytes`.
+const fn decode_utf8(bytes: &[u8], pos: usize) -> Option<(char, usize)> {
+    if pos >= bytes.len() {
+        return None;
+    }
+    let (len, mut value) = match bytes[pos] {
+        b @ 0x00..=0x7f => return Some((b as char, 1)),
+        b @ 0xc0..=0xdf => (2, (b & 0x1f) as u32),
+        b @ 0xe0..=0xef => (3, (b & 0x0f) as u32),
+        b => (4, (b & 0x07) as u32),
+    };
+    let mut i = 1;
+    while i < len {
+        value = value << 6 | (bytes[pos + i] & 0x3f) as u32;
+        i += 1;
+    }
+    match char::from_u32(value) {
+        Some(c) => Some((c, len)),
+        None => None,
+    }
+}
+
+/// Like [`lex`], but for input that may not be valid UTF-8.
+///
+/// An invalid UTF-8 sequence where a token would start is returned as
+/// [`Token::InvalidUtf8`]. Inside comments and string literals, invalid
+/// sequences are kept as part of the token, like any other character, and
+/// reported by [`diagnostics_bytes`].
+pub fn lex_bytes(s: &[u8]) -> (Token, usize) {
+    lex_bytes_with(s, DEFAULT_OPTIONS)
+}
+
+fn lex_bytes_with(s: &[u8], options: LexOptions) -> (Token, usize) {
+    match lex_token(s, options) {
+        (Token::Identifier, len) => {
+            let text = core::str::from_utf8(&s[..len]).unwrap();
+            match Keyword::from_identifier(text) {
+                Some(keyword) => (Token::Keyword(keyword), len),
+                None => (Token::Identifier, len),
+            }
+        }
+        (token, len) => resolve(s, token, len),
+    }
+}
+
+/// Source text that `lex_token` can decode characters from.
+trait Input: Copy {
+    /// Decodes the character at `pos`, returning it, its length in bytes, and
+    /// whether it was valid UTF-8. Invalid sequences decode as U+FFFD.
+    fn decode(self, pos: usize) -> Option<(char, usize, bool)>;
+}
+
+impl Input for &str {
+    fn decode(self, pos: usize) -> Option<(char, usize, bool)> {
+        let c = self[pos..].chars().next()?;
+        Some((c, c.len_utf8(), true))
+    }
+}
+
+impl Input for &[u8] {
+    fn decode(self, pos: usize) -> Option<(char, usize, bool)> {
+        let rest = &self[pos..];
+        let window = &rest[..rest.len().min(4)];
+        match core::str::from_utf8(window) {
+            Ok(s) => s.chars().next().map(|c| (c, c.len_utf8(), true)),
+            Err(err) if err.valid_up_to() != 0 => {
+                let c = core::str::from_utf8(&window[..err.valid_up_to()])
+                    .unwrap()
+                    .chars()
+                    .next()
+                    .unwrap();
+                Some((c, c.len_utf8(), true))
+            }
+            Err(err) => Some((
+                core::char::REPLACEMENT_CHARACTER,
+                err.error_len().unwrap_or(window.len()),
+                false,
+            )),
+        }
+    }
+}
+
+/// Counts `len` bytes read by the lexer, for [`test::SCANNED`].
+#[cfg(test)]
+fn record_scan(len: usize) {
+    test::SCANNED.with(|scanned| scanned.set(scanned.get() + len));
+}
+
+#[cfg(not(test))]
+#[inline(always)]
+fn record_scan(_: usize) {}
+
+/// Lexes a single token. `Step::Backtrack` ends the token before the last
+/// character, which gives the same result as lexing the input up to there: the
+/// state the lexer was in before that character, run to the end of input.
+fn lex_token<I: Input>(s: I, options: LexOptions) -> (Token, usize) {
     let mut state = State::Start;
-    let mut iter = s.chars();
-    let (mut cur_pos, mut last_pos) = (0, None);
-    'outer: while let Some(c) = iter.next() {
+    let (mut cur_pos, mut last_pos, mut last_state) = (0, 0, State::Start);
+    'outer: while let Some((c, len, is_valid)) = s.decode(cur_pos) {
+        record_scan(len);
+        if !is_valid {
+            match state {
+                State::Start => return (Token::InvalidUtf8, len),
+                State::Invalid { kind } => return (invalid(kind), cur_pos),
+                _ => {}
+            }
+        }
+        let before = state;
         'inner: loop {
-            match step(state, Some(c)) {
+            match step(state, Some(c), options) {
                 Step::Continue(new_state) => {
-                    last_pos = Some(cur_pos);
-                    cur_pos += c.len_utf8();
+                    last_pos = cur_pos;
+                    last_state = before;
+                    cur_pos += len;
                     state = new_state;
                     continue 'outer;
                 }
@@ -1127,51 +1956,393 @@ pub fn lex(s: &str) -> (Token, usize) {
                     continue 'inner;
                 }
                 Step::Backtrack => {
-                    return lex(&s[..last_pos.unwrap().into()]);
+                    return (finish(last_state), last_pos);
                 }
                 Step::Abort(kind) => {
                     return (kind, cur_pos);
                 }
                 Step::End(kind) => {
-                    cur_pos += c.len_utf8();
+                    cur_pos += len;
                     return (kind, cur_pos);
                 }
             }
         }
     }
+    match step(state, None, options) {
+        Step::Backtrack => (finish(last_state), last_pos),
+        _ => (finish(state), cur_pos),
+    }
+}
+
+/// Runs `state` to completion as if the input ended here.
+const fn finish(mut state: State) -> Token {
     loop {
-        match step(state, None) {
-            Step::Reprocess(new_state) => {
-                state = new_state;
-                continue;
-            }
-            Step::Backtrack => {
-                return lex(&s[..last_pos.unwrap().into()]);
-            }
-            Step::Abort(kind) => {
-                return (kind, cur_pos);
-            }
-            Step::Continue(_) | Step::End(_) => unreachable!(),
+        match step(state, None, DEFAULT_OPTIONS) {
+            Step::Reprocess(new_state) => state = new_state,
+            Step::Abort(token) => return token,
+            Step::Continue(_) | Step::End(_) | Step::Backtrack => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        debug_assert!(start <= end);
+        Span { start, end }
+    }
+
+    pub fn len(self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Length of the byte order mark that strict lexing skips at the start of
+/// `source`.
+fn bom_len(source: &[u8], options: LexOptions) -> usize {
+    match source {
+        [0xef, 0xbb, 0xbf, ..] if options.strict => 3,
+        _ => 0,
+    }
+}
+
+/// Iterator over every token in a source string, paired with its byte span.
+///
+/// Iteration ends (returns `None`) once the whole input has been consumed.
+#[derive(Debug, Clone)]
+pub struct Lexer<'a> {
+    source: &'a str,
+    pos: usize,
+    options: LexOptions,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(source: &'a str) -> Lexer<'a> {
+        Lexer::with_options(source, LexOptions::default())
+    }
+
+    pub fn with_options(source: &'a str, options: LexOptions) -> Lexer<'a> {
+        Lexer {
+            source,
+            pos: bom_len(source.as_bytes(), options),
+            options,
         }
     }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// Byte offset of the next token to be returned.
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.pos >= self.source.len()
+    }
+
+    pub fn text(&self, span: Span) -> &'a str {
+        &self.source[span.start..span.end]
+    }
 }
 
+impl<'a> Iterator for Lexer<'a> {
+    type Item = (Token, Span);
+
+    fn next(&mut self) -> Option<(Token, Span)> {
+        if self.is_at_end() {
+            return None;
+        }
+        let (token, len) = lex_with_options(&self.source[self.pos..], self.options);
+        let span = Span::new(self.pos, self.pos + len);
+        self.pos = span.end;
+        Some((token, span))
+    }
+}
+
+impl<'a> core::iter::FusedIterator for Lexer<'a> {}
+
+/// Like [`Lexer`], but over input that may not be valid UTF-8. See
+/// [`lex_bytes`].
+#[derive(Debug, Clone)]
+pub struct BytesLexer<'a> {
+    source: &'a [u8],
+    pos: usize,
+    options: LexOptions,
+}
+
+impl<'a> BytesLexer<'a> {
+    pub fn new(source: &'a [u8]) -> BytesLexer<'a> {
+        BytesLexer::with_options(source, LexOptions::default())
+    }
+
+    pub fn with_options(source: &'a [u8], options: LexOptions) -> BytesLexer<'a> {
+        BytesLexer {
+            source,
+            pos: bom_len(source, options),
+            options,
+        }
+    }
+
+    pub fn source(&self) -> &'a [u8] {
+        self.source
+    }
+
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.pos >= self.source.len()
+    }
+
+    pub fn bytes(&self, span: Span) -> &'a [u8] {
+        &self.source[span.start..span.end]
+    }
+}
+
+impl<'a> Iterator for BytesLexer<'a> {
+    type Item = (Token, Span);
+
+    fn next(&mut self) -> Option<(Token, Span)> {
+        if self.is_at_end() {
+            return None;
+        }
+        let rest = &self.source[self.pos..];
+        let (token, len) = lex_bytes_with(rest, self.options);
+        let span = Span::new(self.pos, self.pos + len);
+        self.pos = span.end;
+        Some((token, span))
+    }
+}
+
+impl<'a> core::iter::FusedIterator for BytesLexer<'a> {}
+
 #[cfg(test)]
 mod test {
+    extern crate std;
+
     use super::*;
 
     #[test]
     fn test_invalid() {
-        assert_eq!(lex(""), (Token::Invalid, 0));
-        assert_eq!(lex("$"), (Token::Invalid, 1));
-        assert_eq!(lex("$$"), (Token::Invalid, 2));
-        assert_eq!(lex("$0"), (Token::Invalid, 1));
+        use InvalidCharacterKind::*;
+        let invalid = |kind, character| Token::Invalid { kind, character };
+        assert_eq!(lex(""), (Token::Eof, 0));
+        assert_eq!(lex("$"), (invalid(UnknownSymbol, '$'), 1));
+        assert_eq!(lex("$#"), (invalid(UnknownSymbol, '$'), 2));
+        assert_eq!(lex("$0"), (invalid(UnknownSymbol, '$'), 1));
+        assert_eq!(lex("$é"), (invalid(UnknownSymbol, '$'), 1));
+        assert_eq!(lex("éü$"), (invalid(NonAscii, 'é'), 4));
+        assert_eq!(lex("😀 "), (invalid(NonAscii, '😀'), 4));
+        assert_eq!(lex("\u{0}\u{1b}a"), (invalid(ControlCharacter, '\u{0}'), 2));
+        assert_eq!(lex("\u{85}é"), (invalid(ControlCharacter, '\u{85}'), 2));
+        assert_eq!(lex("\u{7f}"), (invalid(ControlCharacter, '\u{7f}'), 1));
+        assert_eq!(lex("\\x"), (invalid(Backslash, '\\'), 1));
+        assert_eq!(lex("\\"), (invalid(Backslash, '\\'), 1));
+        assert_eq!(lex("\r"), (invalid(LoneCarriageReturn, '\r'), 1));
+        assert_eq!(lex("\r\r\n"), (invalid(LoneCarriageReturn, '\r'), 1));
+        assert_eq!(lex("$\r"), (invalid(UnknownSymbol, '$'), 1));
+        assert_eq!(lex_bytes(b"\\\xff"), (invalid(Backslash, '\\'), 1));
+        assert_eq!(lex("é\u{2028}"), (invalid(NonAscii, 'é'), 2));
+        assert_eq!(
+            lex("\u{2028}\u{2029}x"),
+            (invalid(LineSeparator, '\u{2028}'), 6)
+        );
+        assert_eq!(lex_bytes(b"\xc3\xa9\xff"), (invalid(NonAscii, 'é'), 2));
+        assert_linear("$é", LexOptions::default());
+        assert_linear("\u{85}é\u{1}$", LexOptions::default());
     }
 
     #[test]
     fn test_whitespace() {
         assert_eq!(lex(" "), (Token::Whitespace, 1));
         assert_eq!(lex(" \t\r\n"), (Token::Whitespace, 4));
+        assert_eq!(lex(" \r\r\n"), (Token::Whitespace, 1));
+        assert_eq!(lex(" \r"), (Token::Whitespace, 1));
+        assert_eq!(lex(" \r "), (Token::Whitespace, 1));
+        assert_eq!(lex("\r\n\r\n\t"), (Token::Whitespace, 5));
+    }
+
+    #[test]
+    fn test_newlines() {
+        let options = LexOptions {
+            newlines: true,
+            ..LexOptions::default()
+        };
+        let newline = Token::Newline;
+        let lone_cr = Token::Invalid {
+            kind: InvalidCharacterKind::LoneCarriageReturn,
+            character: '\r',
+        };
+        assert_eq!(lex_with_options(" \t\r\n", options), (Token::Whitespace, 2));
+        assert_eq!(lex_with_options("\r\n ", options), (newline, 2));
+        assert_eq!(lex_with_options("\n\n", options), (newline, 1));
+        assert_eq!(lex_with_options("a\n", options), (Token::Identifier, 1));
+
+        let source = "a \n\r\n\t\rb\n";
+        let mut lexer = Lexer::with_options(source, options);
+        for expected in [
+            (Token::Identifier, 0, 1),
+            (Token::Whitespace, 1, 2),
+            (newline, 2, 3),
+            (newline, 3, 5),
+            (Token::Whitespace, 5, 6),
+            (lone_cr, 6, 7),
+            (Token::Identifier, 7, 8),
+            (newline, 8, 9),
+        ] {
+            assert_eq!(
+                lexer.next(),
+                Some((expected.0, Span::new(expected.1, expected.2)))
+            );
+        }
+        assert_eq!(lexer.next(), None);
+        assert!(BytesLexer::with_options(source.as_bytes(), options)
+            .eq(Lexer::with_options(source, options)));
+        assert_linear("\n", options);
+        assert_linear(" \r\n", options);
+    }
+
+    std::thread_local! {
+        /// How many bytes `lex_token` and `dfa::lex_token` have read on this
+        /// thread.
+        pub(crate) static SCANNED: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+    }
+
+    /// Asserts that lexing `unit` repeated many times reads each byte a
+    /// bounded number of times. Relexing the rest of the input for every token
+    /// would read it about as many times as there are tokens.
+    fn assert_linear(unit: &str, options: LexOptions) {
+        let n = 1_000;
+        let source = unit.repeat(n);
+        SCANNED.with(|scanned| scanned.set(0));
+        assert!(Lexer::with_options(&source, options).count() >= n);
+        let scanned = SCANNED.with(|scanned| scanned.get());
+        assert!(
+            scanned <= 2 * source.len(),
+            "{:?}: read {} bytes of {}",
+            unit,
+            scanned,
+            source.len()
+        );
+    }
+
+    #[test]
+    fn test_strict() {
+        use InvalidCharacterKind::*;
+        let strict = LexOptions {
+            strict: true,
+            ..LexOptions::default()
+        };
+        let lex_strict = |s| lex_with_options(s, strict);
+        let invalid = |kind, character| Token::Invalid { kind, character };
+        assert_eq!(lex_strict("// a\tb\n"), (Token::Comment, 6));
+        assert_eq!(lex_strict("// a\r\n"), (Token::Comment, 4));
+        assert_eq!(lex("// a\r\n"), (Token::Comment, 5));
+        assert_eq!(lex_strict("//\r\n"), (Token::Comment, 2));
+        assert_eq!(lex_strict("/// a\r\n"), (Token::DocComment, 5));
+        assert_eq!(
+            lex_strict("// a\u{1}b\n"),
+            (invalid(ControlCharacter, '\u{1}'), 6)
+        );
+        assert_eq!(lex("// a\u{1}b\n"), (Token::Comment, 6));
+        assert_eq!(
+            lex_strict("// a\rb\r\n"),
+            (invalid(LoneCarriageReturn, '\r'), 6)
+        );
+        assert_eq!(lex_strict("// a\r"), (invalid(LoneCarriageReturn, '\r'), 5));
+        assert_eq!(
+            lex_strict("//!\u{7f}"),
+            (invalid(ControlCharacter, '\u{7f}'), 4)
+        );
+        assert_eq!(
+            lex_strict("\\\\\u{2029}"),
+            (invalid(LineSeparator, '\u{2029}'), 5)
+        );
+        assert_eq!(
+            lex_strict("\"\u{85}\" x\n"),
+            (invalid(ControlCharacter, '\u{85}'), 6)
+        );
+        assert_eq!(
+            lex_strict("'\\\u{0}'"),
+            (invalid(ControlCharacter, '\u{0}'), 4)
+        );
+        assert_eq!(
+            lex_strict("\"a\r\n"),
+            (
+                Token::StringLiteral {
+                    is_unterminated: true,
+                    has_invalid_escape: false,
+                },
+                2
+            )
+        );
+        let raw = |is_empty| Token::RawIdentifier {
+            is_empty,
+            is_unterminated: false,
+            has_invalid_escape: false,
+        };
+        assert_eq!(lex_strict(r#"@"""#), (raw(true), 3));
+        assert_eq!(lex_strict(r#"@"a""#), (raw(false), 4));
+        assert_eq!(lex_strict(".**"), (invalid(UnknownSymbol, '*'), 3));
+        assert_eq!(lex_strict(".*"), (Token::DotStar, 2));
+        assert_eq!(lex_strict(".*."), (Token::DotStar, 2));
+        assert_eq!(lex(".**"), (Token::DotStar, 2));
+        let int = |has_invalid_characters| Token::IntegerLiteral {
+            base: IntegerBase::Decimal,
+            is_unterminated: false,
+            has_invalid_characters,
+            has_duplicate_underscore: false,
+        };
+        assert_eq!(lex_strict("0123"), (int(true), 4));
+        assert_eq!(lex("0123"), (int(false), 4));
+        assert_eq!(lex_strict("0"), (int(false), 1));
+        assert_eq!(lex_strict("0_0"), (int(true), 3));
+        assert_eq!(lex("0_0"), (int(false), 3));
+
+        let source = "\u{feff}const a = 1;\r\n// \u{2028}\n";
+        let mut lexer = Lexer::with_options(source, strict);
+        assert_eq!(lexer.offset(), 3);
+        let expected = [
+            (Token::Keyword(Keyword::Const), 3, 8),
+            (Token::Whitespace, 8, 9),
+            (Token::Identifier, 9, 10),
+            (Token::Whitespace, 10, 11),
+            (Token::Equal, 11, 12),
+            (Token::Whitespace, 12, 13),
+            (int(false), 13, 14),
+            (Token::Semicolon, 14, 15),
+            (Token::Whitespace, 15, 17),
+            (invalid(LineSeparator, '\u{2028}'), 17, 23),
+            (Token::Whitespace, 23, 24),
+        ];
+        for expected in expected {
+            assert_eq!(
+                lexer.next(),
+                Some((expected.0, Span::new(expected.1, expected.2)))
+            );
+        }
+        assert_eq!(lexer.next(), None);
+        assert!(BytesLexer::with_options(source.as_bytes(), strict)
+            .eq(Lexer::with_options(source, strict)));
+        assert_eq!(Lexer::new(source).next().unwrap().1, Span::new(0, 3));
+
+        let mut lexer = BytesLexer::with_options(b"\"\xff\x01\xff\"", strict);
+        assert_eq!(
+            lexer.next(),
+            Some((invalid(ControlCharacter, '\u{1}'), Span::new(0, 5)))
+        );
     }
 
     #[test]
@@ -1179,6 +2350,10 @@ mod test {
         assert_eq!(lex("// \n"), (Token::Comment, 3));
         assert_eq!(lex("//hello\n"), (Token::Comment, 7));
         assert_eq!(lex("// hello\n"), (Token::Comment, 8));
+        assert_eq!(lex("//"), (Token::Comment, 2));
+        assert_eq!(lex("////\n"), (Token::Comment, 4));
+        assert_eq!(lex("//// hello\n"), (Token::Comment, 10));
+        assert_eq!(lex("// /// hello"), (Token::Comment, 12));
     }
 
     #[test]
@@ -1186,6 +2361,18 @@ mod test {
         assert_eq!(lex("/// \n"), (Token::DocComment, 4));
         assert_eq!(lex("///hello\n"), (Token::DocComment, 8));
         assert_eq!(lex("/// hello\n"), (Token::DocComment, 9));
+        assert_eq!(lex("///\n"), (Token::DocComment, 3));
+        assert_eq!(lex("///"), (Token::DocComment, 3));
+        assert_eq!(lex("///!"), (Token::DocComment, 4));
+    }
+
+    #[test]
+    fn test_container_doc_comment() {
+        assert_eq!(lex("//! \n"), (Token::ContainerDocComment, 4));
+        assert_eq!(lex("//!hello\n"), (Token::ContainerDocComment, 8));
+        assert_eq!(lex("//!\n"), (Token::ContainerDocComment, 3));
+        assert_eq!(lex("//!"), (Token::ContainerDocComment, 3));
+        assert_eq!(lex("//!/"), (Token::ContainerDocComment, 4));
     }
 
     #[test]
@@ -1198,6 +2385,7 @@ mod test {
     fn test_identifier() {
         fn raw(is_unterminated: bool, has_invalid_escape: bool) -> Token {
             Token::RawIdentifier {
+                is_empty: false,
                 is_unterminated,
                 has_invalid_escape,
             }
@@ -1207,8 +2395,41 @@ mod test {
         assert_eq!(lex("_foo1"), (Token::Identifier, 5));
         assert_eq!(lex(r#"@"foo bar""#), (raw(false, false), 10));
         assert_eq!(lex(r#"@"foo bar\x00""#), (raw(false, false), 14));
+        assert_eq!(lex(r#"@"\u{0}\u{10FFFF}""#), (raw(false, false), 18));
+        assert_eq!(lex(r#"@"\u{0}\u{110000}""#), (raw(false, true), 18));
         assert_eq!(lex(r#"@"foo bar\x0""#), (raw(false, true), 13));
         assert_eq!(lex(r#"@"foo bar"#), (raw(true, false), 9));
+        assert_eq!(lex(r#"@"""#), (raw(false, false), 3));
+    }
+
+    #[test]
+    fn test_keyword() {
+        assert_eq!(lex("fn"), (Token::Keyword(Keyword::Fn), 2));
+        assert_eq!(lex("const "), (Token::Keyword(Keyword::Const), 5));
+        assert_eq!(lex("errdefer"), (Token::Keyword(Keyword::Errdefer), 8));
+        assert_eq!(lex("addrspace("), (Token::Keyword(Keyword::Addrspace), 9));
+        assert_eq!(lex("and"), (Token::Keyword(Keyword::And), 3));
+        assert_eq!(lex("or"), (Token::Keyword(Keyword::Or), 2));
+        assert_eq!(lex("fns"), (Token::Identifier, 3));
+        assert_eq!(lex("Fn"), (Token::Identifier, 2));
+        assert_eq!(lex("_fn"), (Token::Identifier, 3));
+        assert_eq!(lex("@fn"), (Token::Builtin, 3));
+        assert_eq!(
+            lex(r#"@"fn""#),
+            (
+                Token::RawIdentifier {
+                    is_empty: false,
+                    is_unterminated: false,
+                    has_invalid_escape: false,
+                },
+                5
+            )
+        );
+        assert_eq!(Keyword::Callconv.as_str(), "callconv");
+        assert_eq!(
+            Keyword::from_identifier("threadlocal"),
+            Some(Keyword::Threadlocal)
+        );
     }
 
     #[test]
@@ -1237,6 +2458,7 @@ mod test {
         assert_eq!(lex("0b12"), (lit(Bin, false, true, false), 4));
         assert_eq!(lex("0o"), (lit(Oct, true, false, false), 2));
         assert_eq!(lex("0x"), (lit(Hex, true, false, false), 2));
+        assert_eq!(lex("0x1__2"), (lit(Hex, false, false, true), 6));
         assert_eq!(lex("1_"), (lit(Dec, true, false, false), 2));
     }
 
@@ -1306,6 +2528,12 @@ mod test {
         assert_eq!(lex(r#""foo bar\u{1G""#), (lit(false, true), 14));
         assert_eq!(lex(r#""foo bar\u{1G}""#), (lit(false, true), 15));
         assert_eq!(lex(r#""foo bar\u{1F}""#), (lit(false, false), 15));
+        assert_eq!(lex(r#""\u{10FFFF}\u{000041}""#), (lit(false, false), 22));
+        assert_eq!(lex(r#""\u{110000}""#), (lit(false, true), 12));
+        assert_eq!(lex(r#""\u{D800}""#), (lit(false, true), 10));
+        assert_eq!(lex(r#""\u{0000000041}""#), (lit(false, true), 16));
+        assert_eq!(lex(r#""\\u{110000}""#), (lit(false, false), 13));
+        assert_eq!(lex(r#""\x41FFFFFFF""#), (lit(false, false), 13));
     }
 
     #[test]
@@ -1322,6 +2550,8 @@ mod test {
         assert_eq!(lex("'\\x0'"), (lit(false, false, true), 5));
         assert_eq!(lex("'\\xzz'"), (lit(false, false, true), 6));
         assert_eq!(lex("'a'"), (lit(false, false, false), 3));
+        assert_eq!(lex("'\\x00'"), (lit(false, false, false), 6));
+        assert_eq!(lex("'\\u{dfff}'"), (lit(false, false, true), 10));
     }
 
     #[test]
@@ -1357,6 +2587,7 @@ mod test {
         assert_eq!(lex("{"), (Token::LBrace, 1));
         assert_eq!(lex("|"), (Token::Or, 1));
         assert_eq!(lex("}"), (Token::RBrace, 1));
+        assert_eq!(lex("~"), (Token::Tilde, 1));
     }
 
     #[test]
@@ -1368,11 +2599,14 @@ mod test {
         assert_eq!(lex("**"), (Token::Star2, 2));
         assert_eq!(lex("*="), (Token::StarEqual, 2));
         assert_eq!(lex("*%"), (Token::StarPercent, 2));
+        assert_eq!(lex("*|"), (Token::StarOr, 2));
         assert_eq!(lex("++"), (Token::Plus2, 2));
         assert_eq!(lex("+="), (Token::PlusEqual, 2));
         assert_eq!(lex("+%"), (Token::PlusPercent, 2));
+        assert_eq!(lex("+|"), (Token::PlusOr, 2));
         assert_eq!(lex("-="), (Token::MinusEqual, 2));
         assert_eq!(lex("-%"), (Token::MinusPercent, 2));
+        assert_eq!(lex("-|"), (Token::MinusOr, 2));
         assert_eq!(lex(".."), (Token::Dot2, 2));
         assert_eq!(lex(".*"), (Token::DotStar, 2));
         assert_eq!(lex("/="), (Token::SlashEqual, 2));
@@ -1390,10 +2624,190 @@ mod test {
     #[test]
     fn test_punctuation_3() {
         assert_eq!(lex("*%="), (Token::StarPercentEqual, 3));
+        assert_eq!(lex("*|="), (Token::StarOrEqual, 3));
         assert_eq!(lex("+%="), (Token::PlusPercentEqual, 3));
+        assert_eq!(lex("+|="), (Token::PlusOrEqual, 3));
         assert_eq!(lex("-%="), (Token::MinusPercentEqual, 3));
+        assert_eq!(lex("-|="), (Token::MinusOrEqual, 3));
         assert_eq!(lex("..."), (Token::Dot3, 3));
         assert_eq!(lex("<<="), (Token::LAngle2Equal, 3));
+        assert_eq!(lex("<<|"), (Token::LAngle2Or, 3));
         assert_eq!(lex(">>="), (Token::RAngle2Equal, 3));
     }
+
+    #[test]
+    fn test_punctuation_4() {
+        assert_eq!(lex("<<|="), (Token::LAngle2OrEqual, 4));
+    }
+
+    #[test]
+    fn test_lex_bytes() {
+        assert_eq!(lex_bytes(b""), (Token::Eof, 0));
+        assert_eq!(lex_bytes(b"\xff"), (Token::InvalidUtf8, 1));
+        assert_eq!(lex_bytes(b"\xe9t"), (Token::InvalidUtf8, 1));
+        assert_eq!(lex_bytes(b"\xe2\x82"), (Token::InvalidUtf8, 2));
+        assert_eq!(lex_bytes(b"\xed\xa0\x80"), (Token::InvalidUtf8, 1));
+        let dollar = Token::Invalid {
+            kind: InvalidCharacterKind::UnknownSymbol,
+            character: '$',
+        };
+        assert_eq!(lex_bytes(b"$\xff"), (dollar, 1));
+        assert_eq!(lex_bytes(b"abc\xff"), (Token::Identifier, 3));
+        assert_eq!(lex_bytes(b"fn\xff"), (Token::Keyword(Keyword::Fn), 2));
+        assert_eq!(lex_bytes(b"// caf\xe9 au lait\n"), (Token::Comment, 15));
+        assert_eq!(
+            lex_bytes(b"\"\xff\""),
+            (
+                Token::StringLiteral {
+                    is_unterminated: false,
+                    has_invalid_escape: false,
+                },
+                3
+            )
+        );
+        assert_eq!(lex_bytes("é".as_bytes()), lex("é"));
+        assert_eq!(lex_bytes(b"1..2"), lex("1..2"));
+
+        let source = b"x\xff\xfe= 1;";
+        let mut lexer = BytesLexer::new(source);
+        assert_eq!(lexer.next(), Some((Token::Identifier, Span::new(0, 1))));
+        assert_eq!(lexer.next(), Some((Token::InvalidUtf8, Span::new(1, 2))));
+        assert_eq!(lexer.next(), Some((Token::InvalidUtf8, Span::new(2, 3))));
+        assert_eq!(lexer.next(), Some((Token::Equal, Span::new(3, 4))));
+        assert_eq!(lexer.bytes(Span::new(1, 2)), b"\xff");
+        assert_eq!(lexer.count(), 3);
+    }
+
+    #[test]
+    fn test_lexer() {
+        let source = "const x = 1..2; // hi\n";
+        let mut lexer = Lexer::new(source);
+        let mut tokens = [(Token::Eof, Span::default()); 16];
+        let mut n = 0;
+        for (token, span) in &mut lexer {
+            tokens[n] = (token, span);
+            n += 1;
+        }
+        let int = Token::IntegerLiteral {
+            base: IntegerBase::Decimal,
+            is_unterminated: false,
+            has_invalid_characters: false,
+            has_duplicate_underscore: false,
+        };
+        assert_eq!(
+            &tokens[..n],
+            &[
+                (Token::Keyword(Keyword::Const), Span::new(0, 5)),
+                (Token::Whitespace, Span::new(5, 6)),
+                (Token::Identifier, Span::new(6, 7)),
+                (Token::Whitespace, Span::new(7, 8)),
+                (Token::Equal, Span::new(8, 9)),
+                (Token::Whitespace, Span::new(9, 10)),
+                (int, Span::new(10, 11)),
+                (Token::Dot2, Span::new(11, 13)),
+                (int, Span::new(13, 14)),
+                (Token::Semicolon, Span::new(14, 15)),
+                (Token::Whitespace, Span::new(15, 16)),
+                (Token::Comment, Span::new(16, 21)),
+                (Token::Whitespace, Span::new(21, 22)),
+            ]
+        );
+        assert_eq!(lexer.text(tokens[0].1), "const");
+        assert_eq!(lexer.text(tokens[11].1), "// hi");
+        assert!(lexer.is_at_end());
+        assert_eq!(lexer.next(), None);
+        assert_eq!(Lexer::new("").next(), None);
+    }
+
+    #[test]
+    fn test_backtrack() {
+        let int = Token::IntegerLiteral {
+            base: IntegerBase::Decimal,
+            is_unterminated: false,
+            has_invalid_characters: false,
+            has_duplicate_underscore: false,
+        };
+        let float = |base| Token::FloatLiteral {
+            base,
+            is_unterminated: false,
+            has_invalid_characters: false,
+            has_duplicate_underscore: false,
+        };
+        assert_eq!(lex("1.."), (int, 1));
+        let hex = Token::IntegerLiteral {
+            base: IntegerBase::Hexadecimal,
+            is_unterminated: false,
+            has_invalid_characters: false,
+            has_duplicate_underscore: false,
+        };
+        assert_eq!(lex("0x1.."), (hex, 3));
+        assert_eq!(lex("1.5."), (float(FloatBase::Decimal), 3));
+        assert_eq!(lex("0x1.f."), (float(FloatBase::Hexadecimal), 5));
+        assert_eq!(lex("1.5.."), (float(FloatBase::Decimal), 3));
+        assert_eq!(lex("1.5..2"), (float(FloatBase::Decimal), 3));
+        assert_eq!(lex("0x1.f..2"), (float(FloatBase::Hexadecimal), 5));
+        assert_eq!(lex_bytes(b"1..\xff"), (int, 1));
+        assert_linear("1..", LexOptions::default());
+        assert_linear("0x1.f..", LexOptions::default());
+
+        let mut buf = [b'1'; 30_000];
+        for chunk in buf.chunks_mut(3) {
+            chunk[1..].copy_from_slice(b"..");
+        }
+        let source = core::str::from_utf8(&buf).unwrap();
+        let mut count = 0;
+        for (i, (token, span)) in Lexer::new(source).enumerate() {
+            match i % 2 {
+                0 => assert_eq!((token, span.len()), (int, 1)),
+                _ => assert_eq!((token, span.len()), (Token::Dot2, 2)),
+            }
+            count += 1;
+        }
+        assert_eq!(count, 20_000);
+        for (token, span) in BytesLexer::new(&buf) {
+            assert_eq!(span.len(), if token == int { 1 } else { 2 });
+        }
+
+        let mut digits = [b'9'; 30_000];
+        digits[29_998..].copy_from_slice(b"..");
+        let digits = core::str::from_utf8(&digits).unwrap();
+        assert_eq!(lex(digits), (int, digits.len() - 2));
+    }
+
+    const _: () = assert!(matches!(lex_const("foo").0, Token::Identifier));
+    const _: () = assert!(matches!(lex_const("fn f").0, Token::Keyword(Keyword::Fn)));
+
+    #[test]
+    fn test_lex_const() {
+        for s in [
+            "",
+            "foo",
+            "const",
+            "constant",
+            "@\"a\"",
+            "1..2",
+            "0x1.f.",
+            "1.5e+3",
+            "é",
+            "😀x",
+            "\"\\u{1F600}\"",
+            "\"\\u{110000}\"",
+            "@\"\\x00\"",
+            "'\\x'",
+            "// é\n",
+            "\\\\ a",
+            "<<|=",
+            "$$ x",
+            "\t\r\n ",
+            "/// doc",
+        ] {
+            assert_eq!(lex_const(s), lex(s), "{:?}", s);
+        }
+        for keyword in Keyword::ALL {
+            assert_eq!(
+                lex_const(keyword.as_str()),
+                (Token::Keyword(keyword), keyword.as_str().len())
+            );
+        }
+    }
 }
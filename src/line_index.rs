@@ -0,0 +1,227 @@
+use crate::Span;
+use alloc::vec::Vec;
+
+/// How columns are counted.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Encoding {
+    /// Bytes.
+    Utf8,
+    /// UTF-16 code units, as used by the Language Server Protocol.
+    Utf16,
+    /// Unicode scalar values.
+    Utf32,
+}
+
+impl Encoding {
+    fn len(self, c: char) -> usize {
+        match self {
+            Encoding::Utf8 => c.len_utf8(),
+            Encoding::Utf16 => c.len_utf16(),
+            Encoding::Utf32 => 1,
+        }
+    }
+}
+
+/// Zero-based line and column.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+pub struct LineCol {
+    pub line: usize,
+    pub col: usize,
+}
+
+/// Maps between byte offsets and line/column positions in a source string.
+///
+/// Lines are separated by `\n`; a `\r` directly before it belongs to the line
+/// terminator rather than the line's contents.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> LineIndex<'a> {
+        let mut line_starts = Vec::new();
+        line_starts.push(0);
+        line_starts.extend(
+            source
+                .bytes()
+                .enumerate()
+                .filter(|&(_, b)| b == b'\n')
+                .map(|(i, _)| i + 1),
+        );
+        LineIndex {
+            source,
+            line_starts,
+        }
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The contents of `line`, excluding its `\n` or `\r\n` terminator.
+    pub fn line_span(&self, line: usize) -> Option<Span> {
+        let start = *self.line_starts.get(line)?;
+        let end = match self.line_starts.get(line + 1) {
+            Some(&next) if self.source.as_bytes()[..next - 1].ends_with(b"\r") => next - 2,
+            Some(&next) => next - 1,
+            None => self.source.len(),
+        };
+        Some(Span::new(start, end))
+    }
+
+    /// Line containing `offset`. Offsets past the end map to the last line.
+    pub fn line(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        }
+    }
+
+    /// Converts a byte offset to a position. Offsets inside a character are
+    /// rounded down; offsets past the end are clamped. The `\n` of a `\r\n`
+    /// pair is one column past the end of the line, so that it round-trips
+    /// through [`LineIndex::offset`].
+    pub fn line_col(&self, offset: usize, encoding: Encoding) -> LineCol {
+        let offset = self.floor_char_boundary(offset);
+        let line = self.line(offset);
+        let span = self.line_span(line).unwrap();
+        let col: usize = self.source[span.start..offset.min(span.end)]
+            .chars()
+            .map(|c| encoding.len(c))
+            .sum();
+        LineCol {
+            line,
+            col: col + offset.saturating_sub(span.end),
+        }
+    }
+
+    /// Converts a position back to a byte offset. Columns past the end of the
+    /// line are clamped to its `\n`, and columns inside a character (such as
+    /// the middle of a UTF-16 surrogate pair) are rounded down. Returns `None`
+    /// if the line does not exist.
+    pub fn offset(&self, line_col: LineCol, encoding: Encoding) -> Option<usize> {
+        let span = self.line_span(line_col.line)?;
+        let mut col = 0;
+        for (i, c) in self.source[span.start..span.end].char_indices() {
+            col += encoding.len(c);
+            if col > line_col.col {
+                return Some(span.start + i);
+            }
+        }
+        let newline = self
+            .line_starts
+            .get(line_col.line + 1)
+            .map_or(self.source.len(), |&next| next - 1);
+        Some((span.end + (line_col.col - col)).min(newline))
+    }
+
+    pub fn span_to_range(&self, span: Span, encoding: Encoding) -> (LineCol, LineCol) {
+        (
+            self.line_col(span.start, encoding),
+            self.line_col(span.end, encoding),
+        )
+    }
+
+    pub fn range_to_span(&self, range: (LineCol, LineCol), encoding: Encoding) -> Option<Span> {
+        let start = self.offset(range.0, encoding)?;
+        let end = self.offset(range.1, encoding)?;
+        Some(Span::new(start, end.max(start)))
+    }
+
+    /// Column of `offset` as displayed, with tabs advancing to the next
+    /// multiple of `tab_width` and every other character taking one cell.
+    pub fn visual_col(&self, offset: usize, tab_width: usize) -> usize {
+        let offset = self.floor_char_boundary(offset);
+        let span = self.line_span(self.line(offset)).unwrap();
+        self.source[span.start..offset.min(span.end)]
+            .chars()
+            .fold(0, |col, c| match c {
+                '\t' if tab_width != 0 => (col / tab_width + 1) * tab_width,
+                _ => col + 1,
+            })
+    }
+
+    fn floor_char_boundary(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Lexer;
+
+    fn lc(line: usize, col: usize) -> LineCol {
+        LineCol { line, col }
+    }
+
+    #[test]
+    fn test_line_col() {
+        let index = LineIndex::new("a\r\nbé😀c\n\nd");
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.line_span(0), Some(Span::new(0, 1)));
+        assert_eq!(index.line_span(1), Some(Span::new(3, 11)));
+        assert_eq!(index.line_span(2), Some(Span::new(12, 12)));
+        assert_eq!(index.line_span(3), Some(Span::new(13, 14)));
+        assert_eq!(index.line_span(4), None);
+        assert_eq!(index.line_col(1, Encoding::Utf8), lc(0, 1));
+        assert_eq!(index.line_col(2, Encoding::Utf8), lc(0, 2));
+        assert_eq!(index.line_col(3, Encoding::Utf8), lc(1, 0));
+        let c = 10;
+        assert_eq!(index.line_col(c, Encoding::Utf8), lc(1, 7));
+        assert_eq!(index.line_col(c, Encoding::Utf16), lc(1, 4));
+        assert_eq!(index.line_col(c, Encoding::Utf32), lc(1, 3));
+        assert_eq!(index.line_col(c - 1, Encoding::Utf32), lc(1, 2));
+        assert_eq!(index.line_col(100, Encoding::Utf8), lc(3, 1));
+        assert_eq!(index.offset(lc(1, 4), Encoding::Utf16), Some(c));
+        assert_eq!(index.offset(lc(1, 3), Encoding::Utf16), Some(6));
+        assert_eq!(index.offset(lc(1, 3), Encoding::Utf32), Some(c));
+        assert_eq!(index.offset(lc(0, 2), Encoding::Utf8), Some(2));
+        assert_eq!(index.offset(lc(0, 5), Encoding::Utf8), Some(2));
+        assert_eq!(index.offset(lc(1, 9), Encoding::Utf32), Some(11));
+        assert_eq!(index.offset(lc(2, 0), Encoding::Utf8), Some(12));
+        assert_eq!(index.offset(lc(4, 0), Encoding::Utf8), None);
+    }
+
+    #[test]
+    fn test_visual_col() {
+        let index = LineIndex::new("\tab\tc\n  \td");
+        assert_eq!(index.visual_col(1, 4), 4);
+        assert_eq!(index.visual_col(4, 4), 8);
+        assert_eq!(index.visual_col(4, 8), 16);
+        assert_eq!(index.visual_col(9, 4), 4);
+        assert_eq!(index.visual_col(9, 0), 3);
+    }
+
+    #[test]
+    fn test_round_trip_spans() {
+        for source in [
+            "const s = \"é😀\";\r\n\t// ü\nfn f() void {}\n",
+            "// a\r\nx",
+            "\u{feff}//!|*|\r\n-f",
+            "\\\\ é\r\n\r\n\r",
+        ] {
+            let index = LineIndex::new(source);
+            for encoding in [Encoding::Utf8, Encoding::Utf16, Encoding::Utf32] {
+                for (_, span) in Lexer::new(source) {
+                    let range = index.span_to_range(span, encoding);
+                    assert_eq!(index.range_to_span(range, encoding), Some(span));
+                }
+            }
+            for offset in (0..=source.len()).filter(|&i| source.is_char_boundary(i)) {
+                let line_col = index.line_col(offset, Encoding::Utf16);
+                assert_eq!(index.offset(line_col, Encoding::Utf16), Some(offset));
+            }
+        }
+    }
+}
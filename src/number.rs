@@ -0,0 +1,569 @@
+#[cfg(feature = "alloc")]
+use crate::BigUint;
+use crate::{lex, IntegerBase, Token};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum IntegerLiteralError {
+    /// The text does not lex as a single integer literal.
+    NotAnInteger,
+    Unterminated,
+    InvalidCharacters,
+    DuplicateUnderscore,
+    /// The value does not fit in the requested type.
+    Overflow,
+}
+
+fn radix(base: IntegerBase) -> u32 {
+    match base {
+        IntegerBase::Binary => 2,
+        IntegerBase::Octal => 8,
+        IntegerBase::Decimal => 10,
+        IntegerBase::Hexadecimal => 16,
+    }
+}
+
+/// Validates `text` with the lexer and returns the base and digit values.
+fn integer_digits(
+    text: &str,
+) -> Result<(u32, impl Iterator<Item = u32> + '_), IntegerLiteralError> {
+    let base = match lex(text) {
+        (
+            Token::IntegerLiteral {
+                base,
+                is_unterminated,
+                has_invalid_characters,
+                has_duplicate_underscore,
+            },
+            len,
+        ) if len == text.len() => {
+            if is_unterminated {
+                return Err(IntegerLiteralError::Unterminated);
+            }
+            if has_invalid_characters {
+                return Err(IntegerLiteralError::InvalidCharacters);
+            }
+            if has_duplicate_underscore {
+                return Err(IntegerLiteralError::DuplicateUnderscore);
+            }
+            base
+        }
+        _ => return Err(IntegerLiteralError::NotAnInteger),
+    };
+    let digits = match base {
+        IntegerBase::Decimal => text,
+        _ => &text[2..],
+    };
+    let radix = radix(base);
+    Ok((
+        radix,
+        digits
+            .chars()
+            .filter(|&c| c != '_')
+            .map(move |c| c.to_digit(radix).unwrap()),
+    ))
+}
+
+/// Parses the text of an integer literal token into its value.
+pub fn parse_integer_literal(text: &str) -> Result<u128, IntegerLiteralError> {
+    let (radix, digits) = integer_digits(text)?;
+    let mut value: u128 = 0;
+    for digit in digits {
+        value = value
+            .checked_mul(u128::from(radix))
+            .and_then(|value| value.checked_add(u128::from(digit)))
+            .ok_or(IntegerLiteralError::Overflow)?;
+    }
+    Ok(value)
+}
+
+/// Parses the text of an integer literal token into an arbitrary-precision
+/// value, as a `comptime_int` would hold it. Never returns `Overflow`.
+#[cfg(feature = "alloc")]
+pub fn parse_integer_literal_big(text: &str) -> Result<BigUint, IntegerLiteralError> {
+    let (radix, digits) = integer_digits(text)?;
+    let mut value = BigUint::zero();
+    for digit in digits {
+        value.mul_add_small(radix, digit);
+    }
+    Ok(value)
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FloatLiteralError {
+    /// The text does not lex as a single float literal, or uses a `p`
+    /// exponent without a `0x` prefix.
+    NotAFloat,
+    Unterminated,
+    InvalidCharacters,
+    DuplicateUnderscore,
+    /// The value is too large for the requested type.
+    Overflow,
+    /// The value is nonzero but rounds to zero in the requested type.
+    Underflow,
+}
+
+/// A float literal split into its digit runs, which may still contain `_`.
+struct FloatParts<'a> {
+    radix: u32,
+    integer: &'a str,
+    fraction: &'a str,
+    exponent: i64,
+}
+
+fn digits(s: &str, radix: u32) -> impl Iterator<Item = u32> + '_ {
+    s.chars()
+        .filter(|&c| c != '_')
+        .map(move |c| c.to_digit(radix).unwrap())
+}
+
+/// Exponents beyond this magnitude overflow or underflow every format, so
+/// saturating here keeps the arithmetic below from overflowing.
+const MAX_EXPONENT: i64 = 1 << 40;
+
+/// Validates `text` with the lexer and splits it into its parts.
+fn float_parts(text: &str) -> Result<FloatParts<'_>, FloatLiteralError> {
+    match lex(text) {
+        (
+            Token::FloatLiteral {
+                is_unterminated,
+                has_invalid_characters,
+                has_duplicate_underscore,
+                ..
+            },
+            len,
+        ) if len == text.len() => {
+            if is_unterminated {
+                return Err(FloatLiteralError::Unterminated);
+            }
+            if has_invalid_characters {
+                return Err(FloatLiteralError::InvalidCharacters);
+            }
+            if has_duplicate_underscore {
+                return Err(FloatLiteralError::DuplicateUnderscore);
+            }
+        }
+        _ => return Err(FloatLiteralError::NotAFloat),
+    }
+    let (radix, rest, markers) = match text.strip_prefix("0x") {
+        Some(rest) => (16, rest, ['p', 'P']),
+        None => (10, text, ['e', 'E']),
+    };
+    if radix == 10 && text.contains(['p', 'P']) {
+        return Err(FloatLiteralError::NotAFloat);
+    }
+    let (mantissa, exponent) = match rest.find(markers) {
+        Some(i) => (&rest[..i], Some(&rest[i + 1..])),
+        None => (rest, None),
+    };
+    let (integer, fraction) = match mantissa.find('.') {
+        Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+        None => (mantissa, ""),
+    };
+    let exponent = match exponent {
+        Some(exponent) => {
+            let (negative, exponent) = match exponent.as_bytes().first() {
+                Some(b'-') => (true, &exponent[1..]),
+                Some(b'+') => (false, &exponent[1..]),
+                _ => (false, exponent),
+            };
+            let mut value: i64 = 0;
+            for c in exponent.chars().filter(|&c| c != '_') {
+                let digit = c.to_digit(10).ok_or(FloatLiteralError::InvalidCharacters)?;
+                value = (value * 10 + i64::from(digit)).min(MAX_EXPONENT);
+            }
+            match negative {
+                true => -value,
+                false => value,
+            }
+        }
+        None => 0,
+    };
+    Ok(FloatParts {
+        radix,
+        integer,
+        fraction,
+        exponent,
+    })
+}
+
+/// Parameters of an IEEE 754 binary format.
+struct Format {
+    /// Significand bits, including the implicit leading bit.
+    precision: u32,
+    /// Biased exponent of infinity.
+    max_biased_exponent: i64,
+    bias: i64,
+}
+
+const F32: Format = Format {
+    precision: 24,
+    max_biased_exponent: 255,
+    bias: 127,
+};
+
+const F64: Format = Format {
+    precision: 53,
+    max_biased_exponent: 2047,
+    bias: 1023,
+};
+
+/// Rounds `mantissa * 2^exponent` to nearest-even in `format` and returns the
+/// bit pattern. `sticky` means the true value is slightly above that product.
+fn round_binary(
+    mut mantissa: u64,
+    mut exponent: i64,
+    sticky: bool,
+    format: &Format,
+) -> Result<u64, FloatLiteralError> {
+    if mantissa == 0 {
+        return Ok(0);
+    }
+    let shift = mantissa.leading_zeros();
+    mantissa <<= shift;
+    exponent -= i64::from(shift);
+    let min_lsb_exponent = 1 - format.bias - i64::from(format.precision - 1);
+    let lsb_exponent = (exponent + 63 - i64::from(format.precision - 1)).max(min_lsb_exponent);
+    let shift = lsb_exponent - exponent;
+    let (mut q, round_up) = if shift > 64 {
+        (0, false)
+    } else if shift == 64 {
+        let half = 1 << 63;
+        (0, mantissa > half || (mantissa == half && sticky))
+    } else {
+        let q = mantissa >> shift;
+        let rem = mantissa & ((1 << shift) - 1);
+        let half = 1 << (shift - 1);
+        (q, rem > half || (rem == half && (sticky || q & 1 == 1)))
+    };
+    let mut lsb_exponent = lsb_exponent;
+    if round_up {
+        q += 1;
+        if q == 1 << format.precision {
+            q >>= 1;
+            lsb_exponent += 1;
+        }
+    }
+    if q == 0 {
+        return Err(FloatLiteralError::Underflow);
+    }
+    let fraction_bits = format.precision - 1;
+    if q >> fraction_bits == 0 {
+        return Ok(q);
+    }
+    let biased_exponent = lsb_exponent + i64::from(fraction_bits) + format.bias;
+    if biased_exponent >= format.max_biased_exponent {
+        return Err(FloatLiteralError::Overflow);
+    }
+    Ok((biased_exponent as u64) << fraction_bits | (q & ((1 << fraction_bits) - 1)))
+}
+
+fn parse_hex_float(parts: &FloatParts<'_>, format: &Format) -> Result<u64, FloatLiteralError> {
+    let (mut mantissa, mut exponent, mut sticky) = (0u64, parts.exponent, false);
+    for digit in digits(parts.integer, 16) {
+        match mantissa >> 60 {
+            0 => mantissa = mantissa << 4 | u64::from(digit),
+            _ => {
+                sticky |= digit != 0;
+                exponent += 4;
+            }
+        }
+    }
+    for digit in digits(parts.fraction, 16) {
+        if mantissa >> 60 == 0 {
+            mantissa = mantissa << 4 | u64::from(digit);
+            exponent -= 4;
+        } else {
+            sticky |= digit != 0;
+        }
+    }
+    round_binary(mantissa, exponent, sticky, format)
+}
+
+/// Significant digits kept when handing a decimal literal to `core`. Any
+/// decimal that decides the rounding of an `f64` fits in 767 digits; one more
+/// slot holds a nonzero digit standing in for the discarded tail.
+const MAX_DECIMAL_DIGITS: usize = 768;
+
+struct DecimalBuf {
+    buf: [u8; MAX_DECIMAL_DIGITS + 32],
+    len: usize,
+}
+
+impl core::fmt::Write for DecimalBuf {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.len + s.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(core::fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Rewrites a decimal literal as `<digits>e<exponent>` without underscores or
+/// leading zeros, truncating it to a length `core` can round correctly.
+fn normalize_decimal(parts: &FloatParts<'_>) -> (DecimalBuf, bool) {
+    let mut out = DecimalBuf {
+        buf: [0; MAX_DECIMAL_DIGITS + 32],
+        len: 0,
+    };
+    let mut exponent = parts.exponent;
+    let (mut is_zero, mut truncated) = (true, false);
+    let integer = digits(parts.integer, 10).map(|d| (d, false));
+    let fraction = digits(parts.fraction, 10).map(|d| (d, true));
+    for (digit, is_fraction) in integer.chain(fraction) {
+        if is_fraction {
+            exponent -= 1;
+        }
+        if is_zero && digit == 0 {
+            continue;
+        }
+        is_zero = false;
+        if out.len < MAX_DECIMAL_DIGITS - 1 {
+            out.buf[out.len] = b'0' + digit as u8;
+            out.len += 1;
+        } else {
+            truncated |= digit != 0;
+            exponent += 1;
+        }
+    }
+    if is_zero {
+        out.buf[0] = b'0';
+        out.len = 1;
+        return (out, true);
+    }
+    if truncated {
+        out.buf[out.len] = b'1';
+        out.len += 1;
+        exponent -= 1;
+    }
+    core::fmt::Write::write_fmt(&mut out, format_args!("e{}", exponent)).unwrap();
+    (out, false)
+}
+
+fn check_range(
+    is_zero: bool,
+    is_infinite: bool,
+    result_is_zero: bool,
+) -> Result<(), FloatLiteralError> {
+    if is_infinite {
+        Err(FloatLiteralError::Overflow)
+    } else if result_is_zero && !is_zero {
+        Err(FloatLiteralError::Underflow)
+    } else {
+        Ok(())
+    }
+}
+
+/// Parses the text of a float literal token into the nearest `f64`.
+pub fn parse_float_literal_f64(text: &str) -> Result<f64, FloatLiteralError> {
+    let parts = float_parts(text)?;
+    if parts.radix == 16 {
+        return parse_hex_float(&parts, &F64).map(f64::from_bits);
+    }
+    let (buf, is_zero) = normalize_decimal(&parts);
+    let s = core::str::from_utf8(&buf.buf[..buf.len]).unwrap();
+    let value: f64 = s.parse().unwrap();
+    check_range(is_zero, value.is_infinite(), value == 0.0)?;
+    Ok(value)
+}
+
+/// Parses the text of a float literal token into the nearest `f32`.
+pub fn parse_float_literal_f32(text: &str) -> Result<f32, FloatLiteralError> {
+    let parts = float_parts(text)?;
+    if parts.radix == 16 {
+        return parse_hex_float(&parts, &F32).map(|bits| f32::from_bits(bits as u32));
+    }
+    let (buf, is_zero) = normalize_decimal(&parts);
+    let s = core::str::from_utf8(&buf.buf[..buf.len]).unwrap();
+    let value: f32 = s.parse().unwrap();
+    check_range(is_zero, value.is_infinite(), value == 0.0)?;
+    Ok(value)
+}
+
+/// The exact value of a float literal, `mantissa * radix^exponent`, as a
+/// `comptime_float` would hold it. `radix` is 10 for decimal literals and 2
+/// for hexadecimal ones.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct FloatLiteralValue {
+    pub mantissa: BigUint,
+    pub radix: u32,
+    pub exponent: i64,
+}
+
+#[cfg(feature = "alloc")]
+pub fn parse_float_literal_exact(text: &str) -> Result<FloatLiteralValue, FloatLiteralError> {
+    let parts = float_parts(text)?;
+    let mut mantissa = BigUint::zero();
+    let mut exponent = parts.exponent;
+    for digit in digits(parts.integer, parts.radix) {
+        mantissa.mul_add_small(parts.radix, digit);
+    }
+    for digit in digits(parts.fraction, parts.radix) {
+        mantissa.mul_add_small(parts.radix, digit);
+        exponent -= match parts.radix {
+            16 => 4,
+            _ => 1,
+        };
+    }
+    Ok(FloatLiteralValue {
+        mantissa,
+        radix: match parts.radix {
+            16 => 2,
+            _ => 10,
+        },
+        exponent,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_integer_literal() {
+        use IntegerLiteralError::*;
+        assert_eq!(parse_integer_literal("0"), Ok(0));
+        assert_eq!(parse_integer_literal("100"), Ok(100));
+        assert_eq!(parse_integer_literal("001"), Ok(1));
+        assert_eq!(parse_integer_literal("100_000"), Ok(100_000));
+        assert_eq!(parse_integer_literal("0b1_0"), Ok(2));
+        assert_eq!(parse_integer_literal("0o777"), Ok(0o777));
+        assert_eq!(parse_integer_literal("0xdead_BEEF"), Ok(0xdead_beef));
+        assert_eq!(
+            parse_integer_literal("0xffffffffffffffffffffffffffffffff"),
+            Ok(u128::MAX)
+        );
+        assert_eq!(
+            parse_integer_literal("340282366920938463463374607431768211455"),
+            Ok(u128::MAX)
+        );
+        assert_eq!(
+            parse_integer_literal("340282366920938463463374607431768211456"),
+            Err(Overflow)
+        );
+        assert_eq!(parse_integer_literal("0b"), Err(Unterminated));
+        assert_eq!(parse_integer_literal("1_"), Err(Unterminated));
+        assert_eq!(parse_integer_literal("0b12"), Err(InvalidCharacters));
+        assert_eq!(parse_integer_literal("0o8"), Err(InvalidCharacters));
+        assert_eq!(parse_integer_literal("1__0"), Err(DuplicateUnderscore));
+        assert_eq!(parse_integer_literal("0x1__0"), Err(DuplicateUnderscore));
+        assert_eq!(parse_integer_literal("1.0"), Err(NotAnInteger));
+        assert_eq!(parse_integer_literal("1 "), Err(NotAnInteger));
+        assert_eq!(parse_integer_literal("x"), Err(NotAnInteger));
+        assert_eq!(parse_integer_literal(""), Err(NotAnInteger));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_parse_integer_literal_big() {
+        use alloc::string::ToString;
+        let big = parse_integer_literal_big("0x1_0000_0000_0000_0000_0000_0000_0000_0000").unwrap();
+        assert_eq!(big.bit_len(), 129);
+        assert_eq!(big.to_string(), "340282366920938463463374607431768211456");
+        assert_eq!(
+            parse_integer_literal_big("12345").unwrap().to_u128(),
+            Some(12345)
+        );
+        assert_eq!(
+            parse_integer_literal_big("0b2"),
+            Err(IntegerLiteralError::InvalidCharacters)
+        );
+    }
+
+    #[test]
+    fn test_parse_float_literal() {
+        use FloatLiteralError::*;
+        assert_eq!(parse_float_literal_f64("1.5"), Ok(1.5));
+        assert_eq!(parse_float_literal_f64("1_000.5e-7"), Ok(1_000.5e-7));
+        assert_eq!(parse_float_literal_f64("0.1"), Ok(0.1));
+        assert_eq!(parse_float_literal_f64("0.0"), Ok(0.0));
+        assert_eq!(parse_float_literal_f64("1e308"), Ok(1e308));
+        assert_eq!(parse_float_literal_f64("1e309"), Err(Overflow));
+        assert_eq!(parse_float_literal_f64("1e-400"), Err(Underflow));
+        assert_eq!(parse_float_literal_f64("0e999999999999999999"), Ok(0.0));
+        assert_eq!(parse_float_literal_f64("4.9e-324"), Ok(f64::from_bits(1)));
+        assert_eq!(parse_float_literal_f32("0.1"), Ok(0.1f32));
+        assert_eq!(parse_float_literal_f32("3.5e38"), Err(Overflow));
+        assert_eq!(parse_float_literal_f64("1."), Err(Unterminated));
+        assert_eq!(parse_float_literal_f64("1.__5"), Err(DuplicateUnderscore));
+        assert_eq!(parse_float_literal_f64("1"), Err(NotAFloat));
+        assert_eq!(parse_float_literal_f64("1p1"), Err(NotAFloat));
+        assert_eq!(parse_float_literal_f64("0x1p1f"), Err(InvalidCharacters));
+    }
+
+    #[test]
+    fn test_parse_float_literal_long_decimal() {
+        // 2^-1074 * 1.5 rounds up to 2^-1073; the digits past the truncation
+        // point (all that distinguish it from an exact tie) must not be lost.
+        let mut text = [b'0'; 1200];
+        text[..2].copy_from_slice(b"0.");
+        let digits = b"7411367182610044";
+        text[325..325 + digits.len()].copy_from_slice(digits);
+        text[1199] = b'1';
+        let text = core::str::from_utf8(&text).unwrap();
+        assert_eq!(parse_float_literal_f64(text), Ok(f64::from_bits(2)));
+        let mut zeros = [b'0'; 1000];
+        zeros[1] = b'.';
+        zeros[999] = b'1';
+        let text = core::str::from_utf8(&zeros).unwrap();
+        assert_eq!(
+            parse_float_literal_f64(text),
+            Err(FloatLiteralError::Underflow)
+        );
+    }
+
+    #[test]
+    fn test_parse_hex_float_literal() {
+        use FloatLiteralError::*;
+        assert_eq!(parse_float_literal_f64("0x1p0"), Ok(1.0));
+        assert_eq!(parse_float_literal_f64("0x1.8p1"), Ok(3.0));
+        assert_eq!(
+            parse_float_literal_f64("0x1.fp+64"),
+            Ok(1.9375 * 18446744073709551616.0)
+        );
+        assert_eq!(parse_float_literal_f64("0x1p-1074"), Ok(f64::from_bits(1)));
+        assert_eq!(parse_float_literal_f64("0x1p-1075"), Err(Underflow));
+        assert_eq!(
+            parse_float_literal_f64("0x1.8p-1074"),
+            Ok(f64::from_bits(2))
+        );
+        assert_eq!(
+            parse_float_literal_f64("0x1.fffffffffffffp1023"),
+            Ok(f64::MAX)
+        );
+        assert_eq!(
+            parse_float_literal_f64("0x1.fffffffffffff8p1023"),
+            Err(Overflow)
+        );
+        assert_eq!(parse_float_literal_f64("0x1p1024"), Err(Overflow));
+        assert_eq!(parse_float_literal_f64("0x0.0p0"), Ok(0.0));
+        assert_eq!(
+            parse_float_literal_f64("0x1.00000000000008p0"),
+            Ok(1.0),
+            "ties to even"
+        );
+        assert_eq!(
+            parse_float_literal_f64("0x1.000000000000080000000000001p0"),
+            Ok(1.0 + f64::EPSILON),
+            "sticky bit breaks the tie"
+        );
+        assert_eq!(parse_float_literal_f32("0x1.fffffep127"), Ok(f32::MAX));
+        assert_eq!(parse_float_literal_f32("0x1p-149"), Ok(f32::from_bits(1)));
+        assert_eq!(parse_float_literal_f32("0x1p128"), Err(Overflow));
+        assert_eq!(parse_float_literal_f64("0x1.p"), Err(Unterminated));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_parse_float_literal_exact() {
+        let value = parse_float_literal_exact("1_000.5e-7").unwrap();
+        assert_eq!(value.mantissa.to_u128(), Some(10005));
+        assert_eq!((value.radix, value.exponent), (10, -8));
+        let value = parse_float_literal_exact("0x1.fp+64").unwrap();
+        assert_eq!(value.mantissa.to_u128(), Some(0x1f));
+        assert_eq!((value.radix, value.exponent), (2, 60));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{LexOptions, Lexer, Span, Token};
+use alloc::vec::Vec;
+use std::thread;
+
+/// Chunks smaller than this are not worth a thread of their own.
+const MIN_CHUNK_LEN: usize = 1 << 16;
+
+/// Lexes `source` on up to `threads` threads, or as many as are available if
+/// `threads` is zero. Returns the same tokens as [`Lexer`].
+///
+/// The source is split just after newlines. No token other than whitespace
+/// crosses a newline, so each chunk can be lexed on its own; whitespace on
+/// either side of a split is joined back into one token.
+pub fn lex_parallel(source: &str, threads: usize) -> Vec<(Token, Span)> {
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    lex_chunks(source, threads, MIN_CHUNK_LEN)
+}
+
+fn lex_chunks(source: &str, threads: usize, min_chunk_len: usize) -> Vec<(Token, Span)> {
+    let chunk_len = (source.len() / threads).max(min_chunk_len).max(1);
+    let mut bounds = Vec::new();
+    bounds.push(0);
+    let mut start = 0;
+    while let Some(newline) = source.as_bytes()[start..]
+        .get(chunk_len..)
+        .and_then(|rest| rest.iter().position(|&b| b == b'\n'))
+    {
+        start += chunk_len + newline + 1;
+        bounds.push(start);
+    }
+    if start < source.len() {
+        bounds.push(source.len());
+    }
+
+    let chunks: Vec<Vec<(Token, Span)>> = thread::scope(|scope| {
+        let handles: Vec<_> = bounds
+            .windows(2)
+            .map(|bounds| {
+                let lexer = Lexer {
+                    source: &source[..bounds[1]],
+                    pos: bounds[0],
+                    options: LexOptions::default(),
+                };
+                scope.spawn(move || lexer.collect())
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let mut tokens: Vec<(Token, Span)> = Vec::with_capacity(chunks.iter().map(Vec::len).sum());
+    for chunk in &chunks {
+        let mut chunk = &chunk[..];
+        if let (Some((Token::Whitespace, last)), Some(&(Token::Whitespace, first))) =
+            (tokens.last_mut(), chunk.first())
+        {
+            *last = Span::new(last.start, first.end);
+            chunk = &chunk[1..];
+        }
+        tokens.extend_from_slice(chunk);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lex_chunks() {
+        let source = "const a = 1;\n\n  \r\nfn f() void {\n    // hi\n\t\\\\str\n}\n'x\n\"y\n$$\n";
+        let expected: Vec<_> = Lexer::new(source).collect();
+        for threads in 1..=source.len() + 1 {
+            for min_chunk_len in [0, 1, 4, 16] {
+                assert_eq!(
+                    lex_chunks(source, threads, min_chunk_len),
+                    expected,
+                    "{} {}",
+                    threads,
+                    min_chunk_len
+                );
+            }
+        }
+        assert_eq!(lex_parallel(source, 0), expected);
+        assert_eq!(lex_parallel("", 4), []);
+    }
+}
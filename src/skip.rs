@@ -0,0 +1,142 @@
+//! Bulk skipping over runs of bytes that keep the lexer in the same state,
+//! used by the table-driven lexer. Works a word at a time, or sixteen bytes at
+//! a time with SSE2 when the `simd` feature is enabled on x86-64.
+
+use core::convert::TryInto;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Skip {
+    /// Everything up to a `\n`, as in comments and `\\` string lines.
+    Line,
+    /// Spaces, tabs and `\n`.
+    Whitespace,
+    /// ASCII letters, digits and `_`.
+    Identifier,
+}
+
+impl Skip {
+    fn matches(self, b: u8) -> bool {
+        match self {
+            Skip::Line => b != b'\n',
+            Skip::Whitespace => matches!(b, b' ' | b'\t' | b'\n'),
+            Skip::Identifier => b.is_ascii_alphanumeric() || b == b'_',
+        }
+    }
+
+    /// Sets the high bit of every byte of `w` that matches.
+    fn matches_word(self, w: u64) -> u64 {
+        match self {
+            Skip::Line => !eq(w, b'\n') & HIGH,
+            Skip::Whitespace => eq(w, b' ') | eq(w, b'\t') | eq(w, b'\n'),
+            Skip::Identifier => {
+                in_range(w, b'0', b'9') | in_range(w | (LOW * 0x20), b'a', b'z') | eq(w, b'_')
+            }
+        }
+    }
+}
+
+const LOW: u64 = 0x0101_0101_0101_0101;
+const HIGH: u64 = 0x8080_8080_8080_8080;
+
+/// Sets the high bit of every byte of `w` equal to `b`.
+fn eq(w: u64, b: u8) -> u64 {
+    let x = w ^ (LOW * b as u64);
+    !(((x & !HIGH) + !HIGH) | x) & HIGH
+}
+
+/// Sets the high bit of every ASCII byte of `w` that is at least `lo`.
+fn at_least(w: u64, lo: u8) -> u64 {
+    ((w & !HIGH) + LOW * (0x80 - lo) as u64) & !w & HIGH
+}
+
+fn in_range(w: u64, lo: u8, hi: u8) -> u64 {
+    at_least(w, lo) & !at_least(w, hi + 1)
+}
+
+/// Returns the position of the first byte at or after `pos` that does not
+/// match `skip`, or `bytes.len()`.
+pub(crate) fn skip(bytes: &[u8], pos: usize, skip: Skip) -> usize {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    let pos = sse2::skip(bytes, pos, skip);
+    let mut pos = pos;
+    while let Some(chunk) = bytes.get(pos..pos + 8) {
+        let w = u64::from_le_bytes(chunk.try_into().unwrap());
+        let stop = !skip.matches_word(w) & HIGH;
+        if stop != 0 {
+            return pos + stop.trailing_zeros() as usize / 8;
+        }
+        pos += 8;
+    }
+    while pos < bytes.len() && skip.matches(bytes[pos]) {
+        pos += 1;
+    }
+    pos
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod sse2 {
+    use super::Skip;
+    use core::arch::x86_64::*;
+
+    pub(super) fn skip(bytes: &[u8], mut pos: usize, skip: Skip) -> usize {
+        while let Some(chunk) = bytes.get(pos..pos + 16) {
+            // SSE2 is always available on x86-64, and the load reads exactly
+            // the sixteen bytes of `chunk`.
+            let mask = unsafe {
+                let v = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+                let eq = |b: u8| _mm_cmpeq_epi8(v, _mm_set1_epi8(b as i8));
+                let in_range = |v: __m128i, lo: u8, hi: u8| {
+                    _mm_and_si128(
+                        _mm_cmpgt_epi8(v, _mm_set1_epi8(lo as i8 - 1)),
+                        _mm_cmplt_epi8(v, _mm_set1_epi8(hi as i8 + 1)),
+                    )
+                };
+                let matches = match skip {
+                    Skip::Line => _mm_andnot_si128(eq(b'\n'), _mm_set1_epi8(-1)),
+                    Skip::Whitespace => _mm_or_si128(_mm_or_si128(eq(b' '), eq(b'\t')), eq(b'\n')),
+                    Skip::Identifier => _mm_or_si128(
+                        _mm_or_si128(
+                            in_range(v, b'0', b'9'),
+                            in_range(_mm_or_si128(v, _mm_set1_epi8(0x20)), b'a', b'z'),
+                        ),
+                        eq(b'_'),
+                    ),
+                };
+                _mm_movemask_epi8(matches) as u32
+            };
+            let stop = !mask & 0xffff;
+            if stop != 0 {
+                return pos + stop.trailing_zeros() as usize;
+            }
+            pos += 16;
+        }
+        pos
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_skip() {
+        for kind in [Skip::Line, Skip::Whitespace, Skip::Identifier] {
+            let fill = match kind {
+                Skip::Identifier => b'a',
+                Skip::Line | Skip::Whitespace => b' ',
+            };
+            for b in 0..=255 {
+                for i in 0..40 {
+                    let mut buf = [fill; 40];
+                    buf[i] = b;
+                    for start in 0..=i {
+                        let expected = (start..buf.len())
+                            .find(|&j| !kind.matches(buf[j]))
+                            .unwrap_or(buf.len());
+                        assert_eq!(skip(&buf, start, kind), expected, "{:?} {:#x}", kind, b);
+                    }
+                }
+            }
+        }
+    }
+}
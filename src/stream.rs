@@ -0,0 +1,440 @@
+use crate::escape::check_escape_value;
+use crate::{finish, invalid, is_rejected_in_line, step, with_invalid_escape, INVALID_LINE};
+use crate::{InvalidCharacterKind, Keyword, LexOptions, Span, State, Step, Token};
+
+/// Receives tokens from a [`StreamLexer`] as soon as they are complete.
+pub trait TokenSink {
+    fn token(&mut self, token: Token, span: Span);
+}
+
+impl<F: FnMut(Token, Span)> TokenSink for F {
+    fn token(&mut self, token: Token, span: Span) {
+        self(token, span)
+    }
+}
+
+/// Longest keyword, `usingnamespace`, fits with room to spare.
+const KEYWORD_BUF_LEN: usize = 16;
+
+/// Push-based lexer for input that arrives in chunks.
+///
+/// Produces the same tokens and spans as a [`Lexer`](crate::Lexer) with the
+/// same options over the concatenated input (or a
+/// [`BytesLexer`](crate::BytesLexer) for byte chunks), regardless of where the
+/// chunk boundaries fall. Byte chunks may split a UTF-8 sequence.
+#[derive(Clone)]
+pub struct StreamLexer {
+    options: LexOptions,
+    state: State,
+    token_start: usize,
+    pos: usize,
+    /// What lexing the current token up to (but excluding) its last character
+    /// would give, for when `step` asks to backtrack over `1..`-like input or
+    /// a `\r` at the end of whitespace.
+    backtrack: Option<(Token, usize, char)>,
+    keyword_buf: [u8; KEYWORD_BUF_LEN],
+    keyword_len: usize,
+    /// First character of the current token.
+    first: char,
+    /// First character of the current token that strict mode rejects in a
+    /// comment or literal.
+    rejected: Option<char>,
+    /// Digit count and value of the current `\x` or `\u{...}` escape.
+    escape_digits: u32,
+    escape_value: u32,
+    /// Whether an escape in the current token had a value that `step` does
+    /// not check, such as `\u{110000}`.
+    has_invalid_escape_value: bool,
+    utf8_buf: [u8; 4],
+    utf8_len: usize,
+}
+
+impl Default for StreamLexer {
+    fn default() -> StreamLexer {
+        StreamLexer::new()
+    }
+}
+
+impl StreamLexer {
+    pub fn new() -> StreamLexer {
+        StreamLexer::with_options(LexOptions::default())
+    }
+
+    pub fn with_options(options: LexOptions) -> StreamLexer {
+        StreamLexer {
+            options,
+            state: State::Start,
+            token_start: 0,
+            pos: 0,
+            backtrack: None,
+            keyword_buf: [0; KEYWORD_BUF_LEN],
+            keyword_len: 0,
+            first: '\0',
+            rejected: None,
+            escape_digits: 0,
+            escape_value: 0,
+            has_invalid_escape_value: false,
+            utf8_buf: [0; 4],
+            utf8_len: 0,
+        }
+    }
+
+    /// Total number of bytes fed so far.
+    pub fn offset(&self) -> usize {
+        self.pos + self.utf8_len
+    }
+
+    pub fn feed<S: TokenSink>(&mut self, chunk: &str, sink: &mut S) {
+        self.flush_utf8(sink);
+        for c in chunk.chars() {
+            self.push(c, c.len_utf8(), true, sink);
+        }
+    }
+
+    pub fn feed_bytes<S: TokenSink>(&mut self, chunk: &[u8], sink: &mut S) {
+        for &b in chunk {
+            if self.utf8_len != 0 && b & 0xc0 != 0x80 {
+                self.flush_utf8(sink);
+            }
+            self.utf8_buf[self.utf8_len] = b;
+            self.utf8_len += 1;
+            let expected = match self.utf8_buf[0] {
+                0x00..=0x7f => 1,
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => 1,
+            };
+            if self.utf8_len == expected {
+                self.flush_utf8(sink);
+            }
+        }
+    }
+
+    /// Signals the end of input, emitting the final token if there is one.
+    pub fn finish<S: TokenSink>(mut self, sink: &mut S) {
+        self.flush_utf8(sink);
+        loop {
+            match step(
+                core::mem::replace(&mut self.state, State::Start),
+                None,
+                self.options,
+            ) {
+                Step::Reprocess(state) => self.state = state,
+                Step::Backtrack => self.backtrack(sink),
+                Step::Abort(token) => {
+                    if self.token_start < self.pos {
+                        self.emit(token, sink);
+                    }
+                    return;
+                }
+                Step::Continue(_) | Step::End(_) => unreachable!(),
+            }
+        }
+    }
+
+    fn flush_utf8<S: TokenSink>(&mut self, sink: &mut S) {
+        let (buf, len) = (self.utf8_buf, self.utf8_len);
+        self.utf8_len = 0;
+        let mut start = 0;
+        while start < len {
+            match core::str::from_utf8(&buf[start..len]) {
+                Ok(s) => {
+                    for c in s.chars() {
+                        self.push(c, c.len_utf8(), true, sink);
+                    }
+                    start = len;
+                }
+                Err(err) if err.valid_up_to() != 0 => {
+                    let valid = core::str::from_utf8(&buf[start..start + err.valid_up_to()]);
+                    for c in valid.unwrap().chars() {
+                        self.push(c, c.len_utf8(), true, sink);
+                    }
+                    start += err.valid_up_to();
+                }
+                Err(err) => {
+                    let invalid = err.error_len().unwrap_or(len - start);
+                    self.push(core::char::REPLACEMENT_CHARACTER, invalid, false, sink);
+                    start += invalid;
+                }
+            }
+        }
+    }
+
+    /// Lexes one character. Invalid UTF-8 sequences are handled the way
+    /// `lex_bytes` handles them.
+    fn push<S: TokenSink>(&mut self, c: char, len: usize, is_valid: bool, sink: &mut S) {
+        if self.pos == 0 && self.options.strict && c == '\u{feff}' {
+            // Skip a byte order mark, as `Lexer::with_options` does.
+            self.pos = len;
+            self.token_start = len;
+            return;
+        }
+        let mut before = self.state;
+        loop {
+            match self.state {
+                State::Start if !is_valid => {
+                    self.pos += len;
+                    self.emit(Token::InvalidUtf8, sink);
+                    return;
+                }
+                State::Invalid { kind } if !is_valid => {
+                    self.state = State::Start;
+                    self.emit(invalid(kind), sink);
+                    before = self.state;
+                    continue;
+                }
+                _ => {}
+            }
+            let state = core::mem::replace(&mut self.state, State::Start);
+            match step(state, Some(c), self.options) {
+                Step::Continue(next) => {
+                    self.backtrack = match next {
+                        State::NumberDot { .. }
+                        | State::NumberHexDot { .. }
+                        | State::WhitespaceCarriageReturn
+                        | State::LineCarriageReturn => Some((finish(before), self.pos, c)),
+                        _ => None,
+                    };
+                    self.escape(state, next, c);
+                    self.state = next;
+                    self.advance(c, len);
+                    return;
+                }
+                Step::Reprocess(state) => self.state = state,
+                Step::Backtrack => {
+                    self.backtrack(sink);
+                    before = self.state;
+                }
+                Step::Abort(token) => {
+                    self.emit(token, sink);
+                    before = self.state;
+                }
+                Step::End(token) => {
+                    self.advance(c, len);
+                    self.emit(token, sink);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Follows the value of the current `\x` or `\u{...}` escape, checking it
+    /// once the escape ends the way `check_escapes` does.
+    fn escape(&mut self, from: State, to: State, c: char) {
+        let is_unicode = match from {
+            State::StringEscapeHex1 { .. } | State::StringEscapeUnicode2 { .. } => {
+                self.escape_digits = 0;
+                self.escape_value = 0;
+                self.escape_digit(c);
+                return;
+            }
+            State::StringEscapeHex2 { .. } => false,
+            State::StringEscapeUnicode3 { .. } => true,
+            _ => return,
+        };
+        self.escape_digit(c);
+        if !matches!(to, State::StringLiteral { .. }) {
+            return;
+        }
+        let (digits, value) = (self.escape_digits, self.escape_value);
+        if check_escape_value(is_unicode, digits, value, false).is_some() {
+            self.has_invalid_escape_value = true;
+        }
+    }
+
+    fn escape_digit(&mut self, c: char) {
+        if let Some(digit) = c.to_digit(16) {
+            self.escape_digits += 1;
+            self.escape_value = self.escape_value.saturating_mul(16).saturating_add(digit);
+        }
+    }
+
+    fn advance(&mut self, c: char, len: usize) {
+        if self.keyword_len == 0 {
+            self.first = c;
+        }
+        if self.rejected.is_none() && is_rejected_in_line(c) {
+            self.rejected = Some(c);
+        }
+        if self.keyword_len + len <= KEYWORD_BUF_LEN {
+            c.encode_utf8(&mut self.keyword_buf[self.keyword_len..]);
+        }
+        self.keyword_len += len;
+        self.pos += len;
+    }
+
+    /// Emits the token saved before the last character, then relexes that
+    /// character from the start state.
+    fn backtrack<S: TokenSink>(&mut self, sink: &mut S) {
+        let (token, last_pos, last) = self.backtrack.take().unwrap();
+        self.pos = last_pos;
+        self.emit(token, sink);
+        self.push(last, last.len_utf8(), true, sink);
+    }
+
+    fn emit<S: TokenSink>(&mut self, token: Token, sink: &mut S) {
+        let token = match token {
+            INVALID_LINE => {
+                let character = self.rejected.unwrap();
+                let kind = InvalidCharacterKind::of(character);
+                Token::Invalid { kind, character }
+            }
+            Token::Invalid {
+                kind,
+                character: '\n',
+            } => Token::Invalid {
+                kind,
+                character: self.first,
+            },
+            Token::RawIdentifier { .. }
+            | Token::StringLiteral { .. }
+            | Token::CharacterLiteral { .. }
+                if self.has_invalid_escape_value =>
+            {
+                with_invalid_escape(token)
+            }
+            Token::Identifier if self.keyword_len <= KEYWORD_BUF_LEN => {
+                let text = core::str::from_utf8(&self.keyword_buf[..self.keyword_len]).unwrap();
+                Keyword::from_identifier(text).map_or(token, Token::Keyword)
+            }
+            _ => token,
+        };
+        sink.token(token, Span::new(self.token_start, self.pos));
+        self.token_start = self.pos;
+        self.keyword_len = 0;
+        self.rejected = None;
+        self.backtrack = None;
+        self.has_invalid_escape_value = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Lexer;
+
+    fn check(source: &str) {
+        check_with(source, LexOptions::default());
+    }
+
+    /// Feeds `source` split at every possible pair of points and checks the
+    /// output matches lexing it whole.
+    fn check_with(source: &str, options: LexOptions) {
+        let mut expected = [(Token::Eof, Span::default()); 64];
+        let mut n = 0;
+        for item in Lexer::with_options(source, options) {
+            expected[n] = item;
+            n += 1;
+        }
+        for i in 0..=source.len() {
+            for j in i..=source.len() {
+                let mut actual = [(Token::Eof, Span::default()); 64];
+                let mut m = 0;
+                let mut sink = |token, span| {
+                    actual[m] = (token, span);
+                    m += 1;
+                };
+                let mut lexer = StreamLexer::with_options(options);
+                let bytes = source.as_bytes();
+                lexer.feed_bytes(&bytes[..i], &mut sink);
+                lexer.feed_bytes(&bytes[i..j], &mut sink);
+                lexer.feed_bytes(&bytes[j..], &mut sink);
+                lexer.finish(&mut sink);
+                assert_eq!(
+                    &actual[..m],
+                    &expected[..n],
+                    "{:?} split at {} {}",
+                    source,
+                    i,
+                    j
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_lexer() {
+        check("const x = 1..2;");
+        check("0x1..0x2 1.5.. 1.._");
+        check(r#""a\x4\u{1F}b" 'é' @"fn""#);
+        check("// 😀 comment\n/// doc\n//! top\n\\\\ line");
+        check("usingnamespace usingnamespaces a<<|=b");
+        check("0b1__0 1e+5 0x1.fp-3 $$");
+        check(r#""\u{10FFFF}\u{110000}" '\u{D800}' "\u{0000041}""#);
+        check(r#"@"\x00" @"\u{0}" "\x00" @"\x41FF""#);
+    }
+
+    #[test]
+    fn test_stream_lexer_options() {
+        for (newlines, strict) in [(true, false), (false, true), (true, true)] {
+            let options = LexOptions { newlines, strict };
+            check_with("a \n\r\n\t\rb\n 1..2\r\n", options);
+            check_with("\u{feff}// a\u{1}b\r\n.** .*. 0_1", options);
+            check_with("\"a\r\n@\"\" '\\\u{0}' \\\\ \u{2028}\r\n", options);
+            check_with("// \u{1}\r\r\n\u{feff}", options);
+        }
+    }
+
+    #[test]
+    fn test_stream_lexer_str_chunks() {
+        let mut tokens = [(Token::Eof, Span::default()); 4];
+        let mut n = 0;
+        let mut sink = |token, span| {
+            tokens[n] = (token, span);
+            n += 1;
+        };
+        let mut lexer = StreamLexer::new();
+        lexer.feed("co", &mut sink);
+        lexer.feed("nst", &mut sink);
+        lexer.feed(" 1.", &mut sink);
+        lexer.feed(".", &mut sink);
+        assert_eq!(lexer.offset(), 9);
+        lexer.finish(&mut sink);
+        assert_eq!(n, 4);
+        assert_eq!(tokens[0], (Token::Keyword(Keyword::Const), Span::new(0, 5)));
+        assert_eq!(tokens[3], (Token::Dot2, Span::new(7, 9)));
+    }
+
+    #[test]
+    fn test_stream_lexer_matches_lex_bytes() {
+        let source = b"a\xed\xa0\x80$\xff\xe2\x82 // \xc3\n\"\xf0\x9f\" \xf0\x9f";
+        let mut expected = [(Token::Eof, Span::default()); 32];
+        let mut n = 0;
+        for item in crate::BytesLexer::new(source) {
+            expected[n] = item;
+            n += 1;
+        }
+        for i in 0..=source.len() {
+            let mut actual = [(Token::Eof, Span::default()); 32];
+            let mut m = 0;
+            let mut sink = |token, span| {
+                actual[m] = (token, span);
+                m += 1;
+            };
+            let mut lexer = StreamLexer::new();
+            lexer.feed_bytes(&source[..i], &mut sink);
+            lexer.feed_bytes(&source[i..], &mut sink);
+            lexer.finish(&mut sink);
+            assert_eq!(&actual[..m], &expected[..n], "split at {}", i);
+        }
+    }
+
+    #[test]
+    fn test_stream_lexer_invalid_utf8() {
+        let mut tokens = [(Token::Eof, Span::default()); 4];
+        let mut n = 0;
+        let mut sink = |token, span| {
+            tokens[n] = (token, span);
+            n += 1;
+        };
+        let mut lexer = StreamLexer::new();
+        lexer.feed_bytes(b"// \xe9t\xc3", &mut sink);
+        lexer.feed_bytes(b"\n\xff", &mut sink);
+        lexer.finish(&mut sink);
+        assert_eq!(n, 3);
+        assert_eq!(tokens[0], (Token::Comment, Span::new(0, 6)));
+        assert_eq!(tokens[1], (Token::Whitespace, Span::new(6, 7)));
+        assert_eq!(tokens[2], (Token::InvalidUtf8, Span::new(7, 8)));
+    }
+}
@@ -0,0 +1,33 @@
+const Color = enum { red, green, blue };
+
+fn classify(c: u8) u8 {
+    return switch (c) {
+        '0'...'9' => 1,
+        'a'...'z', 'A'...'Z' => 2,
+        else => 0,
+    };
+}
+
+fn sum(items: []const i64) i64 {
+    var total: i64 = 0;
+    for (items, 0..) |item, i| {
+        if (i % 2 == 0) continue;
+        total += item;
+    }
+    outer: while (total > 100) : (total -= 1) {
+        break :outer;
+    }
+    return total;
+}
+
+fn parse(bytes: [*:0]const u8) error{Empty}!usize {
+    const len = std.mem.len(bytes);
+    if (len == 0) return error.Empty;
+    defer {}
+    errdefer |err| std.log.err("{}", .{err});
+    return std.fmt.parseInt(usize, bytes[0..len], 10) catch |err| switch (err) {
+        else => 0,
+    };
+}
+
+const std = @import("std");
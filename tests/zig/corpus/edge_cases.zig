@@ -0,0 +1,13 @@
+//! Container doc comment.
+/// Doc comment.
+//// Four slashes make a plain comment.
+// Plain comment.
+const a = 1..2;
+const b = 0...9;
+const c = x.*.y;
+const d = p&&q;
+const e = 1.5..2;
+const f = a[0x1..0b1];
+const g = @"";
+const h = @TypeOf(a)=>b;
+const i = "backslash \\";
@@ -0,0 +1,13 @@
+//! A small program that greets the world.
+
+const std = @import("std");
+
+/// Prints a greeting to standard output.
+pub fn main() !void {
+    const stdout = std.io.getStdOut().writer();
+    try stdout.print("Hello, {s}!\n", .{"world"});
+}
+
+test "greeting" {
+    try std.testing.expect(true);
+}
@@ -0,0 +1,24 @@
+const decimal = 1_000_000;
+const binary = 0b1010_0101;
+const octal = 0o755;
+const hex = 0xdead_BEEF;
+const float = 3.14159;
+const exponent = 6.022e23;
+const negative_exponent = 1.5e-10;
+const hex_float = 0x1.8p3;
+const hex_exponent = 0x10p-2;
+const range = 1..4;
+
+const string = "tab\there \"quoted\" \\ \x41\u{1F600}";
+const empty = "";
+const unicode = "héllo wörld";
+const char = 'a';
+const escaped_char = '\'';
+const unicode_char = '\u{e9}';
+const hex_char = '\x7f';
+const multiline =
+    \\first line
+    \\  second "line" \n
+    \\
+;
+const @"raw identifier" = @"while";
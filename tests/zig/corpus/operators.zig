@@ -0,0 +1,31 @@
+fn operators(a: u32, b: u32, p: *u32, o: ?u32) void {
+    var x = a + b - a * b / 1 % 2;
+    x += a;
+    x -= b;
+    x *= 2;
+    x /= 2;
+    x %= 3;
+    x = a +% b -% a *% b;
+    x +%= 1;
+    x -%= 1;
+    x *%= 1;
+    x = a +| b -| a *| b;
+    x +|= 1;
+    x -|= 1;
+    x *|= 1;
+    x = a << 1 >> 2 <<| 3;
+    x <<= 1;
+    x >>= 1;
+    x <<|= 1;
+    x = a & b | a ^ ~b;
+    x &= 1;
+    x |= 1;
+    x ^= 1;
+    const c = a == b or a != b and a < b or a <= b or a > b or a >= b;
+    const d = !c;
+    p.* = o.?;
+    const arr = [_]u8{ 1, 2 } ++ [_]u8{3} ** 2;
+    const slice = arr[0..];
+    const e = o orelse 0;
+    _ = .{ c, d, e, slice, x };
+}
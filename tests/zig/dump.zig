@@ -0,0 +1,39 @@
+//! Regenerates the expected token dumps in `corpus/`, using the Zig version
+//! pinned in `corpus/ZIG_VERSION`:
+//!
+//!     zig run tests/zig/dump.zig -- tests/zig/corpus/*.zig
+//!
+//! Each `NAME.zig` gets a `NAME.tokens` that starts with a `# zig VERSION`
+//! line, followed by one `tag start end` line per token from
+//! `std.zig.Tokenizer`, up to and including `eof`.
+
+const std = @import("std");
+const builtin = @import("builtin");
+
+const pinned_version = std.mem.trimRight(u8, @embedFile("corpus/ZIG_VERSION"), "\n");
+
+pub fn main() !void {
+    if (!std.mem.eql(u8, builtin.zig_version_string, pinned_version)) {
+        std.debug.print("expected zig {s}, not {s}\n", .{ pinned_version, builtin.zig_version_string });
+        std.process.exit(1);
+    }
+
+    var arena = std.heap.ArenaAllocator.init(std.heap.page_allocator);
+    defer arena.deinit();
+    const allocator = arena.allocator();
+
+    const args = try std.process.argsAlloc(allocator);
+    for (args[1..]) |path| {
+        const source = try std.fs.cwd().readFileAllocOptions(allocator, path, 1 << 24, null, @alignOf(u8), 0);
+        var out = std.ArrayList(u8).init(allocator);
+        try out.writer().print("# zig {s}\n", .{pinned_version});
+        var tokenizer = std.zig.Tokenizer.init(source);
+        while (true) {
+            const token = tokenizer.next();
+            try out.writer().print("{s} {d} {d}\n", .{ @tagName(token.tag), token.loc.start, token.loc.end });
+            if (token.tag == .eof) break;
+        }
+        const out_path = try std.fmt.allocPrint(allocator, "{s}.tokens", .{path[0 .. path.len - ".zig".len]});
+        try std.fs.cwd().writeFile(.{ .sub_path = out_path, .data = out.items });
+    }
+}